@@ -0,0 +1,78 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_verbosity_flag::{InfoLevel, Verbosity};
+use std::path::PathBuf;
+
+use crate::config::CONFIG_ENV;
+
+/// Download videos with yt-dlp periodically, using the profiles and jobs from the config
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+	/// Path of the config file.
+	/// If not set, the first existing file of `./config.toml`,
+	/// `$XDG_CONFIG_HOME/yt-dlp-tasker/config.toml` and `/etc/yt-dlp-tasker/config.toml` is used.
+	#[arg(short, long, global = true, env = CONFIG_ENV)]
+	pub config: Option<PathBuf>,
+
+	#[command(flatten)]
+	pub verbose: Verbosity<InfoLevel>,
+
+	/// format of the log output.
+	/// The log level can also be set by the `RUST_LOG` environment variable.
+	#[arg(long, global = true, value_enum, default_value_t = LogFormat::Pretty)]
+	pub log_format: LogFormat,
+
+	/// What to do. Run as daemon if not set.
+	#[command(subcommand)]
+	pub command: Option<Commands>
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum LogFormat {
+	/// human readable text
+	Pretty,
+	/// a json object per line
+	Json
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+	/// Run all jobs again and again, running each job when it is due according to its schedule
+	Daemon,
+	/// Run all jobs a single time and exit.
+	/// Exit with an error status if any job has failed.
+	RunOnce {
+		/// Only print the yt-dlp commands of all jobs, without running them.
+		/// Exit with an error status if any job can not be loaded.
+		#[arg(long)]
+		dry_run: bool
+	},
+	/// Check the config and the local jobs and print all problems found.
+	/// Exit with an error status if any error was found.
+	Validate {
+		/// also exit with an error status if any warning was found
+		#[arg(long)]
+		deny_warnings: bool
+	},
+	/// List all download/profile combinations of the local and remote jobs
+	ListJobs,
+	/// Print the yt-dlp command of a download with the given profile, without running it
+	ShowCommand {
+		/// name of the download
+		download: String,
+		/// name of the profile
+		profile: String
+	},
+	/// Show the last runs of the jobs, newest first
+	History {
+		/// only show jobs of the download with this name
+		#[arg(short, long)]
+		download: Option<String>,
+		/// only show jobs of the profile with this name
+		#[arg(short, long)]
+		profile: Option<String>,
+		/// maximum number of runs shown per job
+		#[arg(short = 'n', long, default_value_t = 10)]
+		limit: usize
+	}
+}
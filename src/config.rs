@@ -0,0 +1,293 @@
+use std::{
+	env, fs, mem,
+	num::NonZeroUsize,
+	path::{self, Path, PathBuf},
+	time::Duration
+};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+	http::HttpConfig, job_log::JobLogConfig, policy::RemotePolicy, remote::RemoteJob,
+	schedule::Schedule, serde_helper::*
+};
+
+/// Environment variable, which can be used to set the path of the config file.
+pub const CONFIG_ENV: &str = "YT_DLP_TASKER_CONFIG";
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Download {
+	/// Name of download job.
+	/// The name is also used for the archive
+	pub name: String,
+	/// url to dowload videos.
+	/// Can be a single entry or a vec
+	#[serde(deserialize_with = "vec_or_one")]
+	pub profile: Vec<String>,
+	/// Video url to be downloaded. You can use anything here which is supported by yt-dlp.
+	/// Can be a single entry or a vec
+	#[serde(deserialize_with = "vec_or_one")]
+	pub url: Vec<String>,
+	/// When this download should run.
+	/// Overrides the schedule of the profile.
+	pub schedule: Option<Schedule>,
+	/// Maximum time yt-dlp may run for this download.
+	/// Overrides the timeout of the profile.
+	#[serde(default, with = "humantime_serde")]
+	pub timeout: Option<Duration>,
+	/// Namespace of the remote job, which has defined this download.
+	/// `None` for local downloads.
+	#[serde(skip)]
+	pub namespace: Option<String>
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+	/// unique name/identifier for this profile
+	pub name: String,
+	/// args which should be passed to yt-dl
+	pub args: Vec<String>,
+	/// If true `--download-archive DOWNLOADNAME-PROFILENAME.txt` is added to the args,
+	/// where `PROFILENAME`` is the `name` field of this struct and `DOWNLOADNAME` is `name` entry of the [Download] struct.
+	/// If false you can still use download archive by manual adding them to [Profile] args field.
+	#[serde(default = "default_true")]
+	pub archive: bool,
+	/// Maximum number of downloads with this profile running at the same time.
+	/// Is additional limited by `max_parallel` of the [Config].
+	pub max_parallel: Option<NonZeroUsize>,
+	/// When downloads with this profile should run.
+	/// If neither the download nor the profile has a schedule, `interval` of the [Config] is used.
+	pub schedule: Option<Schedule>,
+	/// how often a failed download is retried directly (default: `0`)
+	#[serde(default)]
+	pub retries: u32,
+	/// Time to wait before the first retry.
+	/// Is doubled for each further retry and randomized by up to 50 percent (default: `1m`).
+	#[serde(default = "default_retry_backoff", with = "humantime_serde")]
+	pub retry_backoff: Duration,
+	/// Only retry if yt-dlp exits with one of these exit codes.
+	/// If empty, every failure is retried.
+	#[serde(default)]
+	pub retry_on_exit_codes: Vec<i32>,
+	/// Maximum time yt-dlp may run for a download with this profile.
+	/// After this time yt-dlp and all its child processes are terminated.
+	/// Unlimited if not set.
+	#[serde(default, with = "humantime_serde")]
+	pub timeout: Option<Duration>
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	/// path os yt-dlp binary (default: `yt-dlp`)
+	#[serde(default = "default_bin_name")]
+	pub bin_name: String,
+	/// Intervall in which the programm should wait before check for downloads again in seconds,
+	/// messured from start to start.
+	/// Is only used for downloads without a schedule.
+	/// The program will always wait at least 2 minutes before checking for dowload again.
+	#[serde(default = "default_23h_in_seconds")]
+	pub interval: u64,
+	/// maximum number of downloads running at the same time (default: `1`)
+	#[serde(default = "default_max_parallel")]
+	pub max_parallel: NonZeroUsize,
+	/// If set, a failed job runs again after this time, instead of waiting for its next regular run.
+	#[serde(default, with = "humantime_serde")]
+	pub retry_failed_after: Option<Duration>,
+	/// Time to wait after terminating yt-dlp on timeout, before it is killed (default: `10s`)
+	#[serde(default = "default_timeout_grace_period", with = "humantime_serde")]
+	pub timeout_grace_period: Duration,
+	/// Directory where the archives are stored and yt-dlp is executed.
+	/// A relative path is relative to the directory of the config file.
+	/// If not set, the current working directory is used.
+	/// Is always absolute after [load_config] was called.
+	pub data_dir: Option<PathBuf>,
+	/// Id of this instance, send with the reports of remote jobs.
+	/// If not set, a random id is created and stored at the data dir.
+	pub instance_id: Option<String>,
+	/// The output of yt-dlp is written to `logs/DOWNLOADNAME-PROFILENAME/TIME.log` at the data dir.
+	/// Old log files are removed according to these settings.
+	#[serde(default)]
+	pub job_log: JobLogConfig,
+	/// restrictions of the yt-dlp args of profiles loaded from remote jobs
+	#[serde(default)]
+	pub remote_policy: RemotePolicy,
+	/// settings of the http client used to load remote jobs
+	#[serde(default)]
+	pub http: HttpConfig,
+	// Profile which is used to download the video.
+	// Array is also supported, so you can download it with differnet settings/profiles (as example as audio and video)
+	pub profile: Vec<Profile>,
+	pub download: Vec<Download>,
+	/// Urls from which additional jobs are loaded.
+	/// Each entry can be a plain url or a table with headers and authentication.
+	#[serde(default, deserialize_with = "vec_or_one")]
+	pub remote_job: Vec<RemoteJob>,
+	/// Glob patterns of additional job files, like `conf.d/*.toml`.
+	/// Relative patterns are relative to the directory of the config file.
+	#[serde(default, deserialize_with = "vec_or_one")]
+	pub include: Vec<String>
+}
+
+impl Config {
+	/// jobs defined directly at the config
+	pub fn local_task_source(&self) -> TaskSource {
+		TaskSource {
+			profile: self.profile.clone(),
+			download: self.download.clone(),
+			remote_job: Vec::new(),
+			include: Vec::new()
+		}
+	}
+
+	/// resolve a path relative to the data dir
+	pub fn data_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+		match &self.data_dir {
+			Some(data_dir) => data_dir.join(path),
+			None => path.as_ref().to_owned()
+		}
+	}
+}
+
+fn default_bin_name() -> String {
+	"yt-dlp".into()
+}
+
+fn default_23h_in_seconds() -> u64 {
+	82800
+}
+
+fn default_max_parallel() -> NonZeroUsize {
+	NonZeroUsize::MIN
+}
+
+fn default_retry_backoff() -> Duration {
+	Duration::from_secs(60)
+}
+
+fn default_timeout_grace_period() -> Duration {
+	Duration::from_secs(10)
+}
+
+fn default_true() -> bool {
+	true
+}
+
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TaskSource {
+	/// Can be omitted by remote jobs, which only use local profiles.
+	#[serde(default)]
+	pub profile: Vec<Profile>,
+	pub download: Vec<Download>,
+	/// further remote jobs, which are loaded recursively
+	#[serde(default, deserialize_with = "vec_or_one")]
+	pub remote_job: Vec<RemoteJob>,
+	/// Glob patterns of further job files, which are loaded recursively.
+	/// Relative patterns are relative to the directory of the file.
+	/// Can not be used by remote jobs.
+	#[serde(default, deserialize_with = "vec_or_one")]
+	pub include: Vec<String>
+}
+
+/// sources referenced by a [TaskSource]
+#[derive(Debug, Default)]
+pub struct NestedSources {
+	pub remote_job: Vec<RemoteJob>,
+	pub include: Vec<String>
+}
+
+impl TaskSource {
+	/// remove the referenced sources from `self`
+	pub fn take_nested(&mut self) -> NestedSources {
+		NestedSources {
+			remote_job: mem::take(&mut self.remote_job),
+			include: mem::take(&mut self.include)
+		}
+	}
+}
+
+/// make a relative glob pattern relative to `dir`
+pub fn resolve_pattern(dir: &Path, pattern: &str) -> String {
+	if Path::new(pattern).is_relative() {
+		let dir = glob::Pattern::escape(&dir.to_string_lossy());
+		Path::new(&dir).join(pattern).to_string_lossy().into_owned()
+	} else {
+		pattern.to_owned()
+	}
+}
+
+/// Where profiles referenced by remote downloads without `local:` prefix are searched.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileLookup {
+	/// only the profiles of the remote job
+	#[default]
+	Remote,
+	/// the profiles of the remote job first, then the local profiles
+	RemoteFirst,
+	/// the local profiles first, then the profiles of the remote job
+	LocalFirst
+}
+
+/// Locations where the config file is searched, if no path was set explicitly:
+/// `./config.toml`, `$XDG_CONFIG_HOME/yt-dlp-tasker/config.toml`
+/// and `/etc/yt-dlp-tasker/config.toml`.
+fn config_search_path() -> Vec<PathBuf> {
+	let mut paths = vec![PathBuf::from("config.toml")];
+	// relative paths are invalid according to the XDG Base Directory Specification
+	let config_home = env::var_os("XDG_CONFIG_HOME")
+		.map(PathBuf::from)
+		.filter(|path| path.is_absolute())
+		.or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+	if let Some(config_home) = config_home {
+		paths.push(config_home.join(env!("CARGO_PKG_NAME")).join("config.toml"));
+	}
+	paths.push(
+		Path::new("/etc")
+			.join(env!("CARGO_PKG_NAME"))
+			.join("config.toml")
+	);
+	paths
+}
+
+/// return the first existing config file of the search path
+pub fn find_config() -> anyhow::Result<PathBuf> {
+	let search_path = config_search_path();
+	search_path
+		.iter()
+		.find(|path| path.is_file())
+		.cloned()
+		.with_context(|| {
+			format!(
+				"no config file found at {search_path:?}. Use `--config` or ${CONFIG_ENV} to set the path."
+			)
+		})
+}
+
+pub fn load_config(path: &Path) -> anyhow::Result<Config> {
+	let config = fs::read_to_string(path)
+		.with_context(|| format!("failed to read config file {path:?}"))?;
+	let mut config: Config = toml::from_str(&config)
+		.with_context(|| format!("failed to parse config file {path:?}"))?;
+	let config_dir = path.parent().unwrap_or(Path::new(""));
+	// yt-dlp runs in the data dir, so paths passed to it must not be relative to the data dir
+	if let Some(data_dir) = &config.data_dir {
+		let data_dir = config_dir.join(data_dir);
+		config.data_dir = Some(
+			path::absolute(&data_dir)
+				.with_context(|| format!("failed to resolve data dir {data_dir:?}"))?
+		);
+	}
+	config.http.resolve_paths(config_dir);
+	for remote_job in &mut config.remote_job {
+		remote_job.resolve_paths(config_dir);
+	}
+	for pattern in &mut config.include {
+		*pattern = resolve_pattern(config_dir, pattern);
+	}
+	Ok(config)
+}
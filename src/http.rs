@@ -0,0 +1,127 @@
+use std::{
+	path::{Path, PathBuf},
+	time::Duration
+};
+
+use anyhow::{bail, Context};
+use bytesize::ByteSize;
+use reqwest::{
+	blocking::{Client, ClientBuilder},
+	Proxy
+};
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use reqwest::{Certificate, Identity};
+use serde::Deserialize;
+
+/// Settings of the http client used to load remote jobs.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+	/// Pem files with additional CA certificates, which are trusted.
+	/// A relative path is relative to the directory of the config file.
+	pub ca_certs: Vec<PathBuf>,
+	/// Pem file with the client certificate used for mutual tls.
+	/// Requires `client_key`.
+	pub client_cert: Option<PathBuf>,
+	/// Pem file with the private key of the client certificate.
+	/// The `native-tls` backend does only support PKCS#8 keys.
+	pub client_key: Option<PathBuf>,
+	/// Proxy for all requests, like `http://proxy:8080` or `socks5://proxy:1080`.
+	/// If not set, the `HTTP_PROXY` and `HTTPS_PROXY` environment variables are used.
+	pub proxy: Option<String>,
+	/// timeout of a single request (default: `1m`)
+	#[serde(with = "humantime_serde")]
+	pub timeout: Duration,
+	/// Time after which loading all remote jobs is aborted (default: `5m`).
+	/// The remote jobs are loaded at the same time.
+	#[serde(with = "humantime_serde")]
+	pub total_timeout: Duration,
+	/// maximum size of a response body (default: `10 MiB`)
+	pub max_body_size: ByteSize
+}
+
+impl Default for HttpConfig {
+	fn default() -> Self {
+		Self {
+			ca_certs: Vec::new(),
+			client_cert: None,
+			client_key: None,
+			proxy: None,
+			timeout: Duration::from_secs(60),
+			total_timeout: Duration::from_secs(5 * 60),
+			max_body_size: ByteSize::mib(10)
+		}
+	}
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+	std::fs::read(path).with_context(|| format!("failed to read {path:?}"))
+}
+
+#[cfg(feature = "native-tls")]
+fn identity(cert: &Path, key: &Path) -> anyhow::Result<Identity> {
+	Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)
+		.context("failed to load client certificate")
+}
+
+#[cfg(all(feature = "rustls-tls", not(feature = "native-tls")))]
+fn identity(cert: &Path, key: &Path) -> anyhow::Result<Identity> {
+	let mut pem = read(cert)?;
+	pem.push(b'\n');
+	pem.extend(read(key)?);
+	Identity::from_pem(&pem).context("failed to load client certificate")
+}
+
+impl HttpConfig {
+	/// make relative paths relative to `dir`
+	pub fn resolve_paths(&mut self, dir: &Path) {
+		let paths = self
+			.ca_certs
+			.iter_mut()
+			.chain(&mut self.client_cert)
+			.chain(&mut self.client_key);
+		for path in paths {
+			*path = dir.join(&*path);
+		}
+	}
+
+	#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+	fn tls(&self, mut builder: ClientBuilder) -> anyhow::Result<ClientBuilder> {
+		for path in &self.ca_certs {
+			let certs = Certificate::from_pem_bundle(&read(path)?)
+				.with_context(|| format!("failed to parse certificates of {path:?}"))?;
+			for cert in certs {
+				builder = builder.add_root_certificate(cert);
+			}
+		}
+		match (&self.client_cert, &self.client_key) {
+			(None, None) => {},
+			(Some(cert), Some(key)) => builder = builder.identity(identity(cert, key)?),
+			_ => bail!("`client_cert` and `client_key` must be set together")
+		}
+		Ok(builder)
+	}
+
+	#[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+	fn tls(&self, builder: ClientBuilder) -> anyhow::Result<ClientBuilder> {
+		if !self.ca_certs.is_empty()
+			|| self.client_cert.is_some()
+			|| self.client_key.is_some()
+		{
+			bail!("tls settings require the `native-tls` or `rustls-tls` feature");
+		}
+		Ok(builder)
+	}
+
+	/// create a http client with these settings
+	pub fn client(&self) -> anyhow::Result<Client> {
+		let mut builder = self.tls(Client::builder().timeout(self.timeout))?;
+		if let Some(proxy) = &self.proxy {
+			builder = builder.proxy(
+				Proxy::all(proxy).with_context(|| format!("invalid proxy {proxy:?}"))?
+			);
+		}
+		builder.build().context("failed to create http client")
+	}
+}
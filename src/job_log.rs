@@ -0,0 +1,103 @@
+use std::{
+	cmp::Reverse,
+	fs::{self, create_dir_all, File},
+	io::Write,
+	path::{Path, PathBuf},
+	process::Command,
+	time::{Duration, SystemTime}
+};
+
+use anyhow::Context;
+use bytesize::ByteSize;
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::{
+	config::{Config, Download, Profile},
+	tasks::JobId
+};
+
+/// Rotation settings of the log files, where the output of yt-dlp is stored.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobLogConfig {
+	/// log files older than this are removed (default: `30d`)
+	#[serde(with = "humantime_serde")]
+	pub max_age: Duration,
+	/// Maximum total size of the log files of a single download/profile combination.
+	/// If it is exceeded, the oldest log files are removed (default: `100 MiB`).
+	pub max_size: ByteSize
+}
+
+impl Default for JobLogConfig {
+	fn default() -> Self {
+		Self {
+			max_age: Duration::from_secs(30 * 24 * 60 * 60),
+			max_size: ByteSize::mib(100)
+		}
+	}
+}
+
+/// directory of the log files of a download/profile combination
+fn log_dir(config: &Config, download: &Download, profile: &Profile) -> PathBuf {
+	config
+		.data_path("logs")
+		.join(JobId::new(download, profile).file_name())
+}
+
+/// Remove log files of `dir`, which are older than `max_age`
+/// or exceed `max_size`, starting with the oldest one.
+fn rotate(dir: &Path, log_config: &JobLogConfig) -> anyhow::Result<()> {
+	let mut files = Vec::new();
+	for entry in
+		fs::read_dir(dir).with_context(|| format!("failed to read dir {dir:?}"))?
+	{
+		let entry = entry.with_context(|| format!("failed to read dir {dir:?}"))?;
+		let path = entry.path();
+		if path.extension().is_some_and(|ext| ext == "log") {
+			let metadata = entry
+				.metadata()
+				.with_context(|| format!("failed to read metadata of {path:?}"))?;
+			files.push((metadata.modified()?, metadata.len(), path));
+		}
+	}
+	// newest first
+	files.sort_unstable_by_key(|file| Reverse(file.0));
+
+	let now = SystemTime::now();
+	let mut total_size = 0;
+	for (modified, size, path) in files {
+		total_size += size;
+		let too_old = now
+			.duration_since(modified)
+			.is_ok_and(|age| age > log_config.max_age);
+		if too_old || total_size > log_config.max_size.as_u64() {
+			fs::remove_file(&path)
+				.with_context(|| format!("failed to remove {path:?}"))?;
+		}
+	}
+	Ok(())
+}
+
+/// Create a new log file for the download/profile combination,
+/// after rotating the old ones, and write the command into it.
+/// Return the path of the file and the opened file.
+pub fn create_log_file(
+	config: &Config,
+	download: &Download,
+	profile: &Profile,
+	cmd: &Command
+) -> anyhow::Result<(PathBuf, File)> {
+	let dir = log_dir(config, download, profile);
+	create_dir_all(&dir).with_context(|| format!("failed to create dir {dir:?}"))?;
+	rotate(&dir, &config.job_log).context("failed to rotate log files")?;
+	let path = dir.join(format!(
+		"{}.log",
+		Local::now().format("%Y-%m-%dT%H-%M-%S%.3f")
+	));
+	let mut file =
+		File::create(&path).with_context(|| format!("failed to create {path:?}"))?;
+	writeln!(file, "run: {cmd:?}\n")
+		.with_context(|| format!("failed to write {path:?}"))?;
+	Ok((path, file))
+}
@@ -1,272 +1,333 @@
 use std::{
-	collections::HashMap,
-	fs::{self, create_dir_all},
-	process::Command,
+	collections::HashSet,
+	fs, io,
+	path::Path,
+	process::ExitCode,
 	thread::sleep,
 	time::{Duration, Instant}
 };
 
 use anyhow::{bail, Context};
-use reqwest::blocking::Client;
-use serde::Deserialize;
+use chrono::{DateTime, Local};
+use clap::Parser;
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+mod cli;
+mod config;
+mod http;
+mod job_log;
+mod policy;
+mod remote;
+mod report;
+mod schedule;
 mod serde_helper;
-use serde_helper::*;
+mod sources;
+mod state;
+mod tasks;
+mod validate;
+use cli::{Cli, Commands, LogFormat};
+use config::{find_config, load_config, Config};
+use schedule::next_run as next_run_of;
+use sources::load_jobs;
+use state::{RunStatus, StateStore};
+use tasks::{build_command, run_all, run_filtered, JobId};
+use validate::{validate_config, Severity};
 
-#[derive(Clone, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-struct Download {
-	/// Name of download job.
-	/// The name is also used for the archive
-	name: String,
-	/// url to dowload videos.
-	/// Can be a single entry or a vec
-	#[serde(deserialize_with = "vec_or_one")]
-	profile: Vec<String>,
-	/// Video url to be downloaded. You can use anything here which is supported by yt-dlp.
-	/// Can be a single entry or a vec
-	#[serde(deserialize_with = "vec_or_one")]
-	url: Vec<String>
-}
-
-#[derive(Clone, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-struct Profile {
-	/// unique name/identifier for this profile
-	name: String,
-	/// args which should be passed to yt-dl
-	args: Vec<String>,
-	/// If true `--download-archive DOWNLOADNAME-PROFILENAME.txt` is added to the args,
-	/// where `PROFILENAME`` is the `name` field of this struct and `DOWNLOADNAME` is `name` entry of the [Download] struct.
-	/// If false you can still use download archive by manual adding them to [Profile] args field.
-	#[serde(default = "default_true")]
-	archive: bool
-}
-
-#[derive(Clone, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-struct Config {
-	/// path os yt-dlp binary (default: `yt-dlp`)
-	#[serde(default = "default_bin_name")]
-	bin_name: String,
-	/// Intervall in which the programm should wait before check for downloads again in seconds,
-	/// messured from start to start.
-	/// The program will always wait at least 2 minutes before checking for dowload again.
-	#[serde(default = "default_23h_in_seconds")]
-	interval: u64,
-	// Profile which is used to download the video.
-	// Array is also supported, so you can download it with differnet settings/profiles (as example as audio and video)
-	profile: Vec<Profile>,
-	download: Vec<Download>,
-	#[serde(default, deserialize_with = "vec_or_one")]
-	remote_job: Vec<String>
-}
-
-fn default_bin_name() -> String {
-	"yt-dlp".into()
-}
-
-fn default_23h_in_seconds() -> u64 {
-	82800
-}
-
-fn default_true() -> bool {
-	true
-}
+/// format used to print times to the user
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
-#[derive(Clone, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-struct TaskSource {
-	profile: Vec<Profile>,
-	download: Vec<Download>
-}
-
-struct Tasks {
-	profiles: HashMap<String, Profile>,
-	download: Vec<Download>
-}
-
-impl Tasks {
-	/// run all task and download all videos with associated settings
-	fn run_all(&self, config: &Config) {
-		// download
-		let mut errors = Vec::new();
-		for download_config in &self.download {
-			for profile_name in &download_config.profile {
-				let profile = self.profiles.get(profile_name).unwrap();
-				let res = download(config, download_config, profile).with_context(|| {
-					format!(
-						"Falied to process {:?} with profile {:?}",
-						download_config.name, profile.name
-					)
-				});
-				if let Err(err) = res {
-					eprintln!("{err:?}");
-					errors.push(err);
-				};
-				println!("\n\n\n\n\n\n")
-			}
-		}
-
-		// print error again as summary
-		// otherwise the user will not be able to find it at wall of text
-		for error in errors {
-			eprintln!("{error:?}\n");
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	init_logging(&cli);
+	let command = cli.command.unwrap_or(Commands::Daemon);
+	let result = cli
+		.config
+		.map_or_else(find_config, Ok)
+		.and_then(|config_path| match command {
+			Commands::Daemon => daemon(&config_path),
+			Commands::RunOnce { dry_run } => run_once(&config_path, dry_run),
+			Commands::Validate { deny_warnings } => validate(&config_path, deny_warnings),
+			Commands::ListJobs => list_jobs(&config_path),
+			Commands::ShowCommand { download, profile } => {
+				show_command(&config_path, &download, &profile)
+			},
+			Commands::History {
+				download,
+				profile,
+				limit
+			} => history(&config_path, download.as_deref(), profile.as_deref(), limit)
+		});
+	match result {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(err) => {
+			error!("{err:?}");
+			ExitCode::FAILURE
 		}
 	}
 }
 
-impl TryFrom<TaskSource> for Tasks {
-	type Error = anyhow::Error;
-
-	fn try_from(value: TaskSource) -> Result<Self, Self::Error> {
-		let mut hash_profiles: HashMap<String, Profile> =
-			HashMap::with_capacity(value.profile.len());
-
-		// convert profile to hashmap
-		for profile in value.profile {
-			let profile_name = profile.name.clone();
-			if hash_profiles
-				.insert(profile_name.clone(), profile)
-				.is_some()
-			{
-				bail!("duplicate profile name {} at config", profile_name)
-			}
-		}
-
-		// check if all profile refs are valid
-		for download in &value.download {
-			for profile_name in &download.profile {
-				hash_profiles.get(profile_name).with_context(|| {
-					format!(
-						"can not find profile {:?} at download {:?}",
-						profile_name, download.name
-					)
-				})?;
-			}
-		}
-		Ok(Self {
-			profiles: hash_profiles,
-			download: value.download
-		})
+fn init_logging(cli: &Cli) {
+	let filter = EnvFilter::builder()
+		.with_default_directive(cli.verbose.tracing_level_filter().into())
+		.from_env_lossy();
+	let subscriber = tracing_subscriber::fmt()
+		.with_env_filter(filter)
+		.with_writer(io::stderr);
+	match cli.log_format {
+		LogFormat::Pretty => subscriber.init(),
+		LogFormat::Json => subscriber
+			.json()
+			.with_current_span(true)
+			.with_span_list(false)
+			.init()
 	}
 }
 
-fn main() {
+/// run all jobs again and again, when they are due, until the process is killed
+fn daemon(config_path: &Path) -> anyhow::Result<()> {
+	// fail directly if the config is invalid at start.
+	// Later the config is reloaded and errors are only logged,
+	// so the daemon keeps running if the config is edited.
+	load_config(config_path)?;
 	loop {
 		let start_time = Instant::now();
-		let intervall = match run() {
+		let wait_time = load_config(config_path).and_then(|config| {
+			let state = StateStore::open(&config)?;
+			Ok(match run_due(&config, &state) {
+				Some(next_run) => (next_run - Local::now()).to_std().unwrap_or_default(),
+				None => Duration::from_secs(config.interval)
+			})
+		});
+		let wait_time = match wait_time {
+			Ok(value) => value,
 			Err(err) => {
-				eprintln!("{err}");
-				300
-			},
-			Ok(value) => value
+				error!("{err:?}");
+				Duration::from_secs(300)
+			}
 		};
 		let duration = start_time.elapsed();
-		println!(
+		info!(
+			duration_secs = duration.as_secs(),
 			"process download in {} minutes and {} seconds",
 			duration.as_secs() / 60,
 			duration.as_secs() % 60
 		);
-		let wait_time = (intervall - duration.as_secs()).max(120);
-		println!("next download in {} minutes", wait_time / 60);
-		sleep(Duration::from_secs(wait_time));
-		println!("\n\n\n\n\n\n");
+		let wait_time = wait_time.max(Duration::from_secs(120));
+		info!(
+			wait_secs = wait_time.as_secs(),
+			"next download in {} minutes",
+			wait_time.as_secs() / 60
+		);
+		sleep(wait_time);
 	}
 }
 
-/// a single download run
-fn run() -> anyhow::Result<u64> {
-	let config: Config = basic_toml::from_str(&fs::read_to_string("config.toml")?)?;
-
-	let local_job = Tasks::try_from(TaskSource {
-		profile: config.profile.clone(),
-		download: config.download.clone()
-	});
-	let local_job = match local_job.context("failed to load local jobs") {
-		Ok(value) => Some(value),
-		Err(err) => {
-			eprintln!("{err:?}");
-			None
-		}
-	};
-
-	let client = Client::new();
-	let remote_jobs: Vec<_> = config
-		.remote_job
+/// Run all jobs which are due, according to their schedule and their last run at `state`.
+/// Jobs which have never run before are always due.
+/// Return when the next job is due.
+fn run_due(config: &Config, state: &StateStore) -> Option<DateTime<Local>> {
+	let (jobs, errors) = load_jobs(config);
+	let now = Local::now();
+	let due: HashSet<JobId> = jobs
 		.iter()
-		.filter_map(|url| {
-			match get_remote_job(&client, url)
-				.with_context(|| format!("failed to load remote job at {url:?}"))
-			{
-				Ok(value) => Some((url.clone(), value)),
-				Err(err) => {
-					eprintln!("{err:?}");
-					None
-				}
+		.flat_map(|(_, job)| job.jobs())
+		.filter(|(download, profile)| {
+			match state.last_run(&JobId::new(download, profile)) {
+				None => true,
+				Some(last_run) => next_run_of(config, download, profile, &last_run)
+					.is_some_and(|next| next <= now)
 			}
 		})
+		.map(|(download, profile)| JobId::new(download, profile))
 		.collect();
-	drop(client);
+	if !due.is_empty() {
+		run_filtered(config, state, &jobs, |download, profile| {
+			due.contains(&JobId::new(download, profile))
+		});
+	}
+	// print the errors of jobs, which could not be loaded, again as summary
+	for error in errors {
+		error!("{error:?}\n");
+	}
 
-	if let Some(job) = local_job {
-		println!("run local jobs:");
-		job.run_all(&config);
+	let mut next_run: Option<DateTime<Local>> = None;
+	for (download, profile) in jobs.iter().flat_map(|(_, job)| job.jobs()) {
+		let next = state
+			.last_run(&JobId::new(download, profile))
+			.and_then(|last_run| next_run_of(config, download, profile, &last_run));
+		if let Some(next) = next {
+			next_run = Some(next_run.map_or(next, |value| value.min(next)));
+		}
 	}
+	next_run
+}
 
-	for (url, job) in remote_jobs {
-		println!("run remote jobs from {url:?}");
-		job.run_all(&config)
+fn run_once(config_path: &Path, dry_run: bool) -> anyhow::Result<()> {
+	let config = load_config(config_path)?;
+	if dry_run {
+		if !print_commands(&config) {
+			bail!("not all jobs could be loaded");
+		}
+		return Ok(());
 	}
+	let state = StateStore::open(&config)?;
+	if !run(&config, &state) {
+		bail!("not all jobs were successful");
+	}
+	Ok(())
+}
 
-	Ok(config.interval)
+fn validate(config_path: &Path, deny_warnings: bool) -> anyhow::Result<()> {
+	let text = fs::read_to_string(config_path)
+		.with_context(|| format!("failed to read config file {config_path:?}"))?;
+	let diagnostics = validate_config(&text);
+	for diagnostic in &diagnostics {
+		println!("{}", diagnostic.format(config_path, &text));
+	}
+	let count = |severity| {
+		diagnostics
+			.iter()
+			.filter(|diagnostic| diagnostic.severity == severity)
+			.count()
+	};
+	let errors = count(Severity::Error);
+	let warnings = count(Severity::Warning);
+	if errors > 0 || (deny_warnings && warnings > 0) {
+		bail!("config {config_path:?} is invalid: {errors} errors, {warnings} warnings");
+	}
+	println!("config {config_path:?} is valid: {warnings} warnings");
+	Ok(())
 }
 
-fn get_remote_job(client: &Client, url: &str) -> anyhow::Result<Tasks> {
-	let source = client
-		.get(url)
-		.send()
-		.context("failed to send request")?
-		.text()
-		.context("failed to load body")?;
-	let source: TaskSource =
-		basic_toml::from_str(&source).context("failed to prase json")?;
-	Tasks::try_from(source)
+fn list_jobs(config_path: &Path) -> anyhow::Result<()> {
+	let config = load_config(config_path)?;
+	let (jobs, errors) = load_jobs(&config);
+	for (source, job) in jobs {
+		println!("{source}:");
+		for download in &job.download {
+			for profile_name in &download.profile {
+				println!("\t{:?} with profile {:?}", download.name, profile_name);
+			}
+		}
+	}
+	if !errors.is_empty() {
+		bail!("not all jobs could be loaded");
+	}
+	Ok(())
 }
 
-fn download(
-	config: &Config,
-	download: &Download,
-	profile: &Profile
+fn show_command(
+	config_path: &Path,
+	download_name: &str,
+	profile_name: &str
 ) -> anyhow::Result<()> {
-	println!(
-		"Download {:?} with profile {:?}",
-		download.name, profile.name
-	);
-	let mut cmd = Command::new(&config.bin_name);
-	if profile.archive {
-		create_dir_all("archives")
-			.with_context(|| "failed to create dir \"archives\"")?;
-		cmd.args([
-			"--download-archive",
-			&format!("archives/{}-{}.txt", download.name, profile.name)
-		]);
-	}
-	cmd.args(&profile.args);
-	cmd.args(&download.url);
-	println!("run: {cmd:?}");
-	let status = cmd.status().with_context(|| "failed to execute command")?;
-	if !status.success() {
-		bail!("command exit with error status {status}");
+	let config = load_config(config_path)?;
+	let (jobs, _) = load_jobs(&config);
+	let (download, profile) = jobs
+		.iter()
+		.find_map(|(_, job)| job.get(download_name, profile_name))
+		.with_context(|| {
+			format!(
+				"can not find download {download_name:?} with profile {profile_name:?}"
+			)
+		})?;
+	println!("{:?}", build_command(&config, download, profile));
+	Ok(())
+}
+
+fn history(
+	config_path: &Path,
+	download_name: Option<&str>,
+	profile_name: Option<&str>,
+	limit: usize
+) -> anyhow::Result<()> {
+	let config = load_config(config_path)?;
+	let state = StateStore::open(&config)?;
+	let jobs = state.jobs();
+	let mut ids: Vec<&JobId> = jobs
+		.keys()
+		.filter(|id| {
+			download_name.is_none_or(|name| id.download == name)
+				&& profile_name.is_none_or(|name| id.profile == name)
+		})
+		.collect();
+	ids.sort();
+	for id in ids {
+		let runs = &jobs[id];
+		let failed = runs
+			.iter()
+			.filter(|run| run.status != RunStatus::Success)
+			.count();
+		let last_success = runs
+			.iter()
+			.rev()
+			.find(|run| run.status == RunStatus::Success)
+			.map(|run| run.start.format(TIME_FORMAT).to_string())
+			.unwrap_or_else(|| "never".to_owned());
+		println!(
+			"{id}: {} runs, {failed} failed, last success: {last_success}",
+			runs.len()
+		);
+		for run in runs.iter().rev().take(limit) {
+			// round to seconds
+			let duration = Duration::from_secs(run.duration().as_secs());
+			print!(
+				"\t{}  {:>8}  {}",
+				run.start.format(TIME_FORMAT),
+				humantime::format_duration(duration).to_string(),
+				run.status
+			);
+			if run.status != RunStatus::Success {
+				if let Some(exit_code) = run.exit_code {
+					print!(" (exit code {exit_code})");
+				}
+			}
+			match &run.error {
+				Some(error) => println!(": {error}"),
+				None => println!()
+			}
+		}
 	}
 	Ok(())
 }
 
+/// a single download run.
+/// Return `false` if any job could not be loaded or has failed.
+fn run(config: &Config, state: &StateStore) -> bool {
+	let (jobs, errors) = load_jobs(config);
+	let success = run_all(config, state, &jobs) && errors.is_empty();
+	// print the errors of jobs, which could not be loaded, again as summary
+	for error in errors {
+		error!("{error:?}\n");
+	}
+	success
+}
+
+/// Print the yt-dlp commands of all jobs, instead of running them.
+/// Return `false` if any job could not be loaded.
+fn print_commands(config: &Config) -> bool {
+	let (jobs, errors) = load_jobs(config);
+	for (source, job) in jobs {
+		println!("# {source}");
+		for (download, profile) in job.jobs() {
+			println!("{:?}", build_command(config, download, profile));
+		}
+	}
+	errors.is_empty()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	#[test]
 	fn config() {
-		let _: Config = basic_toml::from_str(include_str!("../config.toml")).unwrap();
+		let _: Config = toml::from_str(include_str!("../config.toml")).unwrap();
+	}
+
+	#[test]
+	fn cli() {
+		use clap::CommandFactory;
+		Cli::command().debug_assert();
 	}
 }
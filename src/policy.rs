@@ -0,0 +1,271 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+use crate::config::{Download, Profile};
+
+/// Short options of yt-dlp, which take a value.
+/// Needed to split combined short options like `-xo PATH` or `-fbest`.
+const SHORT_WITH_VALUE: &str = "aofpPrRSINtu";
+
+/// options which set output paths
+const OUTPUT_OPTIONS: [&str; 4] = ["-o", "--output", "-P", "--paths"];
+
+/// entries of the data dir, which are used by the tasker itself
+const DATA_DIR_ENTRIES: [&str; 5] =
+	["archives", "cache", "instance_id", "logs", "state.json"];
+
+/// Restrictions of the yt-dlp args of profiles loaded from remote jobs.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RemotePolicy {
+	/// Options remote profiles must not use.
+	/// Abbreviations of long options are also rejected.
+	pub deny_args: Vec<String>,
+	/// If set, remote profiles may only use these options.
+	/// Options must be written out in full.
+	pub allow_args: Option<Vec<String>>,
+	/// Absolute paths, which output paths (`-o`, `-P`) of remote profiles may start with.
+	/// Relative output paths are allowed, if they do not contain `..`
+	/// and do not start with an entry used by the tasker, like `archives` or `state.json`.
+	pub output_prefixes: Vec<PathBuf>
+}
+
+impl Default for RemotePolicy {
+	fn default() -> Self {
+		Self {
+			deny_args: [
+				// run commands
+				"--exec",
+				// can redefine other options
+				"--alias",
+				"--exec-before-download",
+				"--netrc-cmd",
+				"--ffmpeg-location",
+				"--downloader",
+				"--external-downloader",
+				"--downloader-args",
+				"--external-downloader-args",
+				"--use-postprocessor",
+				// pass args to ffmpeg and other postprocessors
+				"--postprocessor-args",
+				"--ppa",
+				"--plugin-dirs",
+				// replace the yt-dlp binary
+				"-U",
+				"--update",
+				"--update-to",
+				// read or write files outside of the output paths
+				"-a",
+				"--batch-file",
+				"--cookies",
+				"--cookies-from-browser",
+				"--config-location",
+				"--config-locations",
+				"--netrc-location",
+				"--load-info-json",
+				"--download-archive",
+				"--print-to-file",
+				"--cache-dir"
+			]
+			.map(ToOwned::to_owned)
+			.into(),
+			allow_args: None,
+			output_prefixes: Vec::new()
+		}
+	}
+}
+
+/// `true` if `name` is `option` or an abbreviation of the long option
+fn matches_option(name: &str, option: &str) -> bool {
+	name == option
+		|| (name.starts_with("--") && name.len() > 2 && option.starts_with(name))
+}
+
+fn is_output_option(name: &str) -> bool {
+	OUTPUT_OPTIONS
+		.iter()
+		.any(|option| matches_option(name, option))
+}
+
+/// Split yt-dlp args into options and their values, as far as needed for the policy.
+/// Positional args (the urls) are skipped.
+fn parse_options(args: &[String]) -> Vec<(String, Option<&str>)> {
+	let mut options = Vec::new();
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		if arg == "--" {
+			break;
+		}
+		if let Some(long) = arg.strip_prefix("--") {
+			let (name, value) = match long.split_once('=') {
+				Some((name, value)) => (format!("--{name}"), Some(value)),
+				None => (arg.clone(), None)
+			};
+			let value = match value {
+				None if is_output_option(&name) => args.next().map(String::as_str),
+				value => value
+			};
+			options.push((name, value));
+		} else if let Some(short) =
+			arg.strip_prefix('-').filter(|short| !short.is_empty())
+		{
+			for (i, c) in short.char_indices() {
+				let name = format!("-{c}");
+				if SHORT_WITH_VALUE.contains(c) {
+					let rest = &short[i + c.len_utf8() ..];
+					let value = match rest {
+						"" => args.next().map(String::as_str),
+						rest => Some(rest)
+					};
+					options.push((name, value));
+					break;
+				}
+				options.push((name, None));
+			}
+		}
+	}
+	options
+}
+
+/// Check that the urls of a remote download can not be mistaken for options.
+/// They are passed after `--` anyway.
+pub fn check_urls(download: &Download) -> anyhow::Result<()> {
+	if let Some(url) = download.url.iter().find(|url| url.starts_with('-')) {
+		bail!("url {url:?} must not start with `-`");
+	}
+	Ok(())
+}
+
+impl RemotePolicy {
+	fn check_output_path(&self, value: &str) -> anyhow::Result<()> {
+		// `-o` and `-P` accept an optional type prefix, like `thumbnail:PATH`
+		let path = match value.split_once(':') {
+			Some((kind, path))
+				if kind.len() > 1
+					&& kind.chars().all(|c| c.is_ascii_lowercase() || c == '_') =>
+			{
+				path
+			},
+			_ => value
+		};
+		// yt-dlp expands `~` and environment variables
+		if path.starts_with('~') || path.contains('$') {
+			bail!("path must not start with `~` or contain `$`");
+		}
+		let path = Path::new(path);
+		if path
+			.components()
+			.any(|component| component == Component::ParentDir)
+		{
+			bail!("path must not contain `..`");
+		}
+		// yt-dlp runs in the data dir
+		if let Some(Component::Normal(first)) = path
+			.components()
+			.find(|component| *component != Component::CurDir)
+		{
+			if DATA_DIR_ENTRIES.iter().any(|entry| first == *entry) {
+				bail!("path must not start with {first:?}, which is used by the tasker");
+			}
+		}
+		if path.is_absolute()
+			&& !self
+				.output_prefixes
+				.iter()
+				.any(|prefix| path.starts_with(prefix))
+		{
+			bail!(
+				"absolute path must start with one of the allowed prefixes {:?}",
+				self.output_prefixes
+			);
+		}
+		Ok(())
+	}
+
+	/// check if the args of a remote profile are allowed
+	pub fn check(&self, profile: &Profile) -> anyhow::Result<()> {
+		for (name, value) in parse_options(&profile.args) {
+			if let Some(denied) = self
+				.deny_args
+				.iter()
+				.find(|denied| matches_option(&name, denied))
+			{
+				bail!(
+					"option {name:?} is denied for remote profiles (matches {denied:?})"
+				);
+			}
+			if let Some(allow_args) = &self.allow_args {
+				if !allow_args.contains(&name) {
+					bail!("option {name:?} is not allowed for remote profiles");
+				}
+			}
+			if is_output_option(&name) {
+				let value =
+					value.with_context(|| format!("missing value of {name:?}"))?;
+				self.check_output_path(value)
+					.with_context(|| format!("output path {value:?} is not allowed"))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn check(policy: &RemotePolicy, args: &[&str]) -> anyhow::Result<()> {
+		let profile: Profile =
+			toml::from_str(&format!("name = \"remote\"\nargs = {:?}", args)).unwrap();
+		policy.check(&profile)
+	}
+
+	#[test]
+	fn policy() {
+		let policy = RemotePolicy {
+			output_prefixes: vec![PathBuf::from("/media")],
+			..Default::default()
+		};
+		for args in [
+			&["-x", "--embed-thumbnail", "-o", "audio/%(title)s.%(ext)s"][..],
+			&["-fbestaudio", "--output=/media/%(title)s.%(ext)s"],
+			&["-P", "thumbnail:thumbs", "-o", "--exec"],
+			&["--no-exec"],
+			&["-o", "archived/%(title)s.%(ext)s"]
+		] {
+			check(&policy, args).unwrap();
+		}
+		for args in [
+			&["--exec", "rm -rf ~"][..],
+			&["--exe=rm -rf ~"],
+			&["-xa", "urls.txt"],
+			&["--cookie", "cookies.txt"],
+			&["-o", "../%(title)s.%(ext)s"],
+			&["-o/etc/%(title)s"],
+			&["--out", "~/%(title)s"],
+			&["--paths", "home:/tmp"],
+			&["-o"],
+			&["-o", "logs/%(title)s.%(ext)s"],
+			&["-P", "./archives"],
+			&["--output", "state.json"],
+			&["--alias", "get-audio", "-x --exec rm"],
+			&["--ppa", "ffmpeg:-i /etc/passwd"],
+			&["-xU"],
+			&["--update-to", "nightly"],
+			&["--upd"],
+			&["--downloader-args", "aria2c:--on-download-complete=/tmp/x"],
+			&["--external-downloader-args=curl:-K /etc/passwd"]
+		] {
+			check(&policy, args).unwrap_err();
+		}
+
+		let policy = RemotePolicy {
+			allow_args: Some(vec!["-x".to_owned(), "-o".to_owned()]),
+			..Default::default()
+		};
+		check(&policy, &["-x", "-o", "%(title)s"]).unwrap();
+		check(&policy, &["-x", "-k"]).unwrap_err();
+	}
+}
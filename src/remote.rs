@@ -0,0 +1,850 @@
+use std::{
+	collections::BTreeMap,
+	env,
+	fmt::{self, Display},
+	fs::{self, create_dir_all},
+	io::{ErrorKind, Read},
+	path::{Path, PathBuf},
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant}
+};
+
+use anyhow::{anyhow, bail, Context};
+use bytesize::ByteSize;
+use chrono::{DateTime, Local};
+use minisign_verify::{PublicKey, Signature};
+use reqwest::{
+	blocking::{Client, RequestBuilder, Response},
+	header::{
+		HeaderMap, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED
+	},
+	Method, StatusCode, Url
+};
+use serde::{
+	de::{self, value::MapAccessDeserializer, DeserializeOwned, MapAccess, Visitor},
+	Deserialize, Deserializer, Serialize
+};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+	config::{Config, NestedSources, ProfileLookup, TaskSource},
+	http::HttpConfig,
+	policy::check_urls,
+	serde_helper::vec_or_one,
+	tasks::{check_name, Tasks},
+	TIME_FORMAT
+};
+
+/// Url from which additional jobs are loaded.
+/// Can be a plain url or a table with additional request settings.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(from = "RemoteJobDef")]
+pub struct RemoteJob {
+	pub url: String,
+	/// Additional urls serving the same file, used if `url` can not be loaded.
+	/// The namespace and the cache are still based on `url`.
+	pub mirrors: Vec<String>,
+	pub mirror_strategy: MirrorStrategy,
+	/// additional http headers send with the request
+	pub headers: BTreeMap<String, String>,
+	/// token used for `Authorization: Bearer TOKEN`
+	pub bearer_token: Option<String>,
+	/// file from which the bearer token is read.
+	/// A relative path is relative to the directory of the config file.
+	pub bearer_token_file: Option<PathBuf>,
+	pub basic_auth: Option<BasicAuth>,
+	/// Minisign public key (the second line of the `.pub` file).
+	/// If set, the file must be signed with the corresponding secret key
+	/// and the signature is loaded from `URL.sig`.
+	pub public_key: Option<String>,
+	/// Where profiles referenced without `local:` prefix are searched (default: `remote`).
+	/// Profiles of the local config can always be referenced by `local:NAME`.
+	pub profile_lookup: ProfileLookup,
+	/// Format of the file.
+	/// If not set, it is chosen by the `Content-Type` header or the file extension of the url,
+	/// with toml as fallback.
+	pub format: Option<Format>,
+	/// Name of the subdirectory for the archives and logs of this remote job,
+	/// so downloads of different sources with the same name do not collide.
+	/// Defaults to a hash of the url.
+	pub namespace: Option<String>,
+	/// If the url can not be loaded, the last successfully loaded version is used instead,
+	/// as long as it is not older than this (default: `7d`).
+	pub max_stale: Duration,
+	/// Url to which a json summary of the runs of the jobs is posted.
+	/// The headers and authentication of the remote job are also used for this request.
+	pub report_url: Option<String>,
+	/// how often a failed report is retried (default: `3`)
+	pub report_retries: u32
+}
+
+enum RemoteJobDef {
+	Url(String),
+	Table(Box<RemoteJobTable>)
+}
+
+/// Chooses the variant by the type of the value,
+/// so errors of the table, like unknown fields, are not replaced by a generic one.
+struct RemoteJobVisitor;
+
+impl<'de> Visitor<'de> for RemoteJobVisitor {
+	type Value = RemoteJobDef;
+
+	fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("an url or a table with an url")
+	}
+
+	fn visit_str<E: de::Error>(self, value: &str) -> Result<RemoteJobDef, E> {
+		Ok(RemoteJobDef::Url(value.to_owned()))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<RemoteJobDef, A::Error> {
+		let table = RemoteJobTable::deserialize(MapAccessDeserializer::new(map))?;
+		Ok(RemoteJobDef::Table(Box::new(table)))
+	}
+}
+
+impl<'de> Deserialize<'de> for RemoteJobDef {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>
+	{
+		deserializer.deserialize_any(RemoteJobVisitor)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteJobTable {
+	url: String,
+	#[serde(default, deserialize_with = "vec_or_one")]
+	mirrors: Vec<String>,
+	#[serde(default)]
+	mirror_strategy: MirrorStrategy,
+	#[serde(default)]
+	headers: BTreeMap<String, String>,
+	bearer_token: Option<String>,
+	bearer_token_file: Option<PathBuf>,
+	basic_auth: Option<BasicAuth>,
+	public_key: Option<String>,
+	#[serde(default)]
+	profile_lookup: ProfileLookup,
+	format: Option<Format>,
+	namespace: Option<String>,
+	#[serde(default = "default_max_stale", with = "humantime_serde")]
+	max_stale: Duration,
+	report_url: Option<String>,
+	#[serde(default = "default_report_retries")]
+	report_retries: u32
+}
+
+fn default_max_stale() -> Duration {
+	Duration::from_secs(7 * 24 * 60 * 60)
+}
+
+fn default_report_retries() -> u32 {
+	3
+}
+
+impl From<RemoteJobDef> for RemoteJob {
+	fn from(value: RemoteJobDef) -> Self {
+		match value {
+			RemoteJobDef::Url(url) => Self {
+				url,
+				mirrors: Vec::new(),
+				mirror_strategy: MirrorStrategy::default(),
+				headers: BTreeMap::new(),
+				bearer_token: None,
+				bearer_token_file: None,
+				basic_auth: None,
+				public_key: None,
+				profile_lookup: ProfileLookup::default(),
+				format: None,
+				namespace: None,
+				max_stale: default_max_stale(),
+				report_url: None,
+				report_retries: default_report_retries()
+			},
+			RemoteJobDef::Table(table) => Self {
+				url: table.url,
+				mirrors: table.mirrors,
+				mirror_strategy: table.mirror_strategy,
+				headers: table.headers,
+				bearer_token: table.bearer_token,
+				bearer_token_file: table.bearer_token_file,
+				basic_auth: table.basic_auth,
+				public_key: table.public_key,
+				profile_lookup: table.profile_lookup,
+				format: table.format,
+				namespace: table.namespace,
+				max_stale: table.max_stale,
+				report_url: table.report_url,
+				report_retries: table.report_retries
+			}
+		}
+	}
+}
+
+/// how the url and the mirrors of a remote job are requested
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MirrorStrategy {
+	/// one after another, until one succeeds
+	#[default]
+	Ordered,
+	/// all at the same time, the first successful response is used
+	Race
+}
+
+/// file format of a remote job
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+	Toml,
+	Json,
+	Yaml
+}
+
+impl Display for Format {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Toml => "toml",
+			Self::Json => "json",
+			Self::Yaml => "yaml"
+		})
+	}
+}
+
+impl Format {
+	fn from_content_type(content_type: &str) -> Option<Self> {
+		// ignore parameters like `; charset=utf-8`
+		let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+		let (kind, subtype) = mime.split_once('/')?;
+		// structured syntax suffix, like `application/vnd.example+json`
+		let subtype = subtype.rsplit('+').next()?;
+		match (kind, subtype) {
+			("application" | "text", "toml") => Some(Self::Toml),
+			("application" | "text", "json") => Some(Self::Json),
+			("application" | "text", "yaml" | "x-yaml") => Some(Self::Yaml),
+			_ => None
+		}
+	}
+
+	fn from_extension(extension: &str) -> Option<Self> {
+		match extension.to_ascii_lowercase().as_str() {
+			"toml" => Some(Self::Toml),
+			"json" => Some(Self::Json),
+			"yaml" | "yml" => Some(Self::Yaml),
+			_ => None
+		}
+	}
+
+	fn from_url(url: &str) -> Option<Self> {
+		let url = Url::parse(url).ok()?;
+		let (_, extension) = url.path().rsplit_once('.')?;
+		Self::from_extension(extension)
+	}
+
+	pub fn from_path(path: &Path) -> Option<Self> {
+		Self::from_extension(path.extension()?.to_str()?)
+	}
+
+	pub fn parse<T: DeserializeOwned>(self, text: &str) -> anyhow::Result<T> {
+		let value = match self {
+			Self::Toml => toml::from_str(text)?,
+			Self::Json => serde_json::from_str(text)?,
+			Self::Yaml => serde_yaml_ng::from_str(text)?
+		};
+		Ok(value)
+	}
+}
+
+/// Credentials for http basic authentication.
+/// At most one of `password`, `password_file` and `password_env` can be set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BasicAuth {
+	pub username: String,
+	pub password: Option<String>,
+	/// file from which the password is read.
+	/// A relative path is relative to the directory of the config file.
+	pub password_file: Option<PathBuf>,
+	/// environment variable from which the password is read
+	pub password_env: Option<String>
+}
+
+/// content of a secret file, without the trailing newline
+fn read_secret(path: &Path) -> anyhow::Result<String> {
+	let secret = fs::read_to_string(path)
+		.with_context(|| format!("failed to read secret file {path:?}"))?;
+	Ok(secret.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+impl BasicAuth {
+	fn password(&self) -> anyhow::Result<Option<String>> {
+		match (&self.password, &self.password_file, &self.password_env) {
+			(None, None, None) => Ok(None),
+			(Some(password), None, None) => Ok(Some(password.clone())),
+			(None, Some(path), None) => read_secret(path).map(Some),
+			(None, None, Some(var)) => env::var(var)
+				.map(Some)
+				.with_context(|| format!("failed to read environment variable {var:?}")),
+			_ => bail!(
+				"only one of `password`, `password_file` and `password_env` can be set"
+			)
+		}
+	}
+}
+
+impl RemoteJob {
+	/// make relative paths relative to `dir`
+	pub fn resolve_paths(&mut self, dir: &Path) {
+		if let Some(path) = &mut self.bearer_token_file {
+			*path = dir.join(&*path);
+		}
+		if let Some(path) = self
+			.basic_auth
+			.as_mut()
+			.and_then(|auth| auth.password_file.as_mut())
+		{
+			*path = dir.join(&*path);
+		}
+	}
+
+	fn bearer_token(&self) -> anyhow::Result<Option<String>> {
+		match (&self.bearer_token, &self.bearer_token_file) {
+			(None, None) => Ok(None),
+			(Some(token), None) => Ok(Some(token.clone())),
+			(None, Some(path)) => read_secret(path).map(Some),
+			(Some(_), Some(_)) => {
+				bail!("only one of `bearer_token` and `bearer_token_file` can be set")
+			}
+		}
+	}
+
+	/// Check and complete a remote job defined by the remote job `parent`.
+	/// It must not access local files, so only http urls and no secret files are allowed.
+	/// The public key is inherited, so a signed remote job can only reference signed ones.
+	pub fn inherit(&mut self, parent: &RemoteJob) -> anyhow::Result<()> {
+		for url in self.urls().chain(self.report_url.as_deref()) {
+			let url = Url::parse(url).with_context(|| format!("invalid url {url:?}"))?;
+			if !matches!(url.scheme(), "http" | "https") {
+				bail!("unsupported scheme {:?}", url.scheme());
+			}
+		}
+		let local_secret = self.bearer_token_file.is_some()
+			|| self.basic_auth.as_ref().is_some_and(|auth| {
+				auth.password_file.is_some() || auth.password_env.is_some()
+			});
+		if local_secret {
+			bail!("secrets can not be read from files or environment variables");
+		}
+		if self.public_key.is_none() {
+			self.public_key = parent.public_key.clone();
+		}
+		Ok(())
+	}
+
+	/// the url followed by the mirrors
+	pub fn urls(&self) -> impl Iterator<Item = &str> {
+		[&self.url]
+			.into_iter()
+			.chain(&self.mirrors)
+			.map(String::as_str)
+	}
+
+	/// the configured namespace or the first 12 hex digits of the sha256 hash of the url
+	pub fn namespace(&self) -> String {
+		match &self.namespace {
+			Some(namespace) => namespace.clone(),
+			None => format!("{:x}", Sha256::digest(&self.url))[.. 12].to_owned()
+		}
+	}
+
+	/// create a request to `url`, including headers and authentication
+	pub fn request(
+		&self,
+		client: &Client,
+		method: Method,
+		url: &str
+	) -> anyhow::Result<RequestBuilder> {
+		let mut request = client.request(method, url);
+		for (name, value) in &self.headers {
+			request = request.header(name, value);
+		}
+		let token = self.bearer_token()?;
+		if let Some(token) = &token {
+			request = request.bearer_auth(token);
+		}
+		if let Some(auth) = &self.basic_auth {
+			if token.is_some() {
+				bail!("only one of bearer and basic authentication can be used");
+			}
+			request = request.basic_auth(&auth.username, auth.password()?);
+		}
+		Ok(request)
+	}
+}
+
+/// last successfully loaded version of a remote job
+#[derive(Clone, Deserialize, Serialize)]
+struct CacheEntry {
+	url: String,
+	/// last time the server has returned or confirmed this version
+	checked: DateTime<Local>,
+	etag: Option<String>,
+	last_modified: Option<String>,
+	body: String,
+	/// minisign signature of the body, if a public key is set
+	#[serde(default)]
+	signature: Option<String>,
+	#[serde(default)]
+	content_type: Option<String>
+}
+
+fn cache_path(config: &Config, url: &str) -> PathBuf {
+	config
+		.data_path("cache")
+		.join("remote_jobs")
+		.join(format!("{:x}.json", Sha256::digest(url)))
+}
+
+fn load_cache(path: &Path) -> anyhow::Result<Option<CacheEntry>> {
+	match fs::read_to_string(path) {
+		Ok(value) => serde_json::from_str(&value)
+			.with_context(|| format!("failed to parse cache file {path:?}")),
+		Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+		Err(err) => {
+			Err(err).with_context(|| format!("failed to read cache file {path:?}"))
+		},
+	}
+}
+
+fn save_cache(path: &Path, entry: &CacheEntry) -> anyhow::Result<()> {
+	if let Some(dir) = path.parent() {
+		create_dir_all(dir).with_context(|| format!("failed to create dir {dir:?}"))?;
+	}
+	// write to a temporary file first, like the state file
+	let tmp_path = path.with_extension("json.tmp");
+	fs::write(&tmp_path, serde_json::to_string(entry)?)
+		.with_context(|| format!("failed to write {tmp_path:?}"))?;
+	fs::rename(&tmp_path, path)
+		.with_context(|| format!("failed to move {tmp_path:?} to {path:?}"))
+}
+
+/// check the minisign `signature` of `body`
+fn verify_signature(
+	public_key: &str,
+	body: &str,
+	signature: Option<&str>
+) -> anyhow::Result<()> {
+	let public_key = PublicKey::from_base64(public_key).context("invalid public key")?;
+	let signature = signature.context("remote job is not signed")?;
+	let signature = Signature::decode(signature).context("failed to decode signature")?;
+	public_key
+		.verify(body.as_bytes(), &signature, false)
+		.context("invalid signature")
+}
+
+/// Verify the signature, if required, and parse the jobs of the cache entry.
+/// The profiles must comply with the remote policy of the config.
+fn parse(
+	config: &Config,
+	remote_job: &RemoteJob,
+	entry: &CacheEntry
+) -> anyhow::Result<(Tasks, NestedSources)> {
+	if let Some(public_key) = &remote_job.public_key {
+		verify_signature(public_key, &entry.body, entry.signature.as_deref())?;
+	}
+	let format = remote_job
+		.format
+		.or_else(|| {
+			entry
+				.content_type
+				.as_deref()
+				.and_then(Format::from_content_type)
+		})
+		.or_else(|| Format::from_url(&entry.url))
+		.unwrap_or(Format::Toml);
+	let mut source: TaskSource = format
+		.parse(&entry.body)
+		.with_context(|| format!("failed to parse {format}"))?;
+	let mut nested = source.take_nested();
+	if !nested.include.is_empty() {
+		bail!("remote jobs can not include files");
+	}
+	for child in &mut nested.remote_job {
+		child
+			.inherit(remote_job)
+			.with_context(|| format!("invalid nested remote job {:?}", child.url))?;
+	}
+	for profile in &source.profile {
+		config.remote_policy.check(profile).with_context(|| {
+			format!("profile {:?} violates the remote policy", profile.name)
+		})?;
+	}
+	for download in &source.download {
+		check_urls(download).with_context(|| {
+			format!("download {:?} violates the remote policy", download.name)
+		})?;
+	}
+	let namespace = remote_job.namespace();
+	check_name(&namespace).with_context(|| format!("invalid namespace {namespace:?}"))?;
+	let mut tasks =
+		Tasks::with_local_profiles(source, &config.profile, remote_job.profile_lookup)?;
+	tasks.set_namespace(&namespace);
+	if remote_job.report_url.is_some() {
+		tasks.report_to = Some(remote_job.clone());
+	}
+	Ok((tasks, nested))
+}
+
+/// load a `file://` remote job and its signature `PATH.sig`
+fn read_file(
+	remote_job: &RemoteJob,
+	url: &str,
+	path: &Path
+) -> anyhow::Result<CacheEntry> {
+	let body =
+		fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+	let signature = match remote_job.public_key {
+		Some(_) => {
+			let mut path = path.as_os_str().to_owned();
+			path.push(".sig");
+			let path = PathBuf::from(path);
+			let signature = fs::read_to_string(&path)
+				.with_context(|| format!("failed to load signature {path:?}"))?;
+			Some(signature)
+		},
+		None => None
+	};
+	Ok(CacheEntry {
+		url: url.to_owned(),
+		checked: Local::now(),
+		etag: None,
+		last_modified: None,
+		body,
+		signature,
+		content_type: None
+	})
+}
+
+/// Send `request` with a timeout, which does not exceed `deadline`.
+fn send(
+	request: RequestBuilder,
+	http: &HttpConfig,
+	deadline: Instant
+) -> anyhow::Result<Response> {
+	let remaining = deadline.saturating_duration_since(Instant::now());
+	if remaining.is_zero() {
+		bail!(
+			"total timeout of {} exceeded",
+			humantime::format_duration(http.total_timeout)
+		);
+	}
+	request
+		.timeout(remaining.min(http.timeout))
+		.send()
+		.context("failed to send request")
+}
+
+/// Read the body of `response`, but not more than `max_size`.
+/// The signature is checked against the raw bytes, so no charset conversion is done.
+fn read_body(response: Response, max_size: ByteSize) -> anyhow::Result<String> {
+	let too_large = || format!("body is larger than {max_size}");
+	if response
+		.content_length()
+		.is_some_and(|len| len > max_size.as_u64())
+	{
+		bail!(too_large());
+	}
+	let mut body = Vec::new();
+	response
+		.take(max_size.as_u64() + 1)
+		.read_to_end(&mut body)
+		.context("failed to load body")?;
+	if body.len() as u64 > max_size.as_u64() {
+		bail!(too_large());
+	}
+	String::from_utf8(body).context("body is not valid utf-8")
+}
+
+/// Request the remote job from `url`, conditional if a cached version exists.
+/// Return `None` if the cached version is still up to date.
+fn fetch(
+	http: &HttpConfig,
+	client: &Client,
+	remote_job: &RemoteJob,
+	url: &str,
+	cache: Option<&CacheEntry>,
+	deadline: Instant
+) -> anyhow::Result<Option<CacheEntry>> {
+	if let Some(path) = Url::parse(url)
+		.ok()
+		.filter(|url| url.scheme() == "file")
+		.and_then(|url| url.to_file_path().ok())
+	{
+		return read_file(remote_job, url, &path).map(Some);
+	}
+	// a cached version without signature can not be used after a public key was added
+	let cache = cache
+		.filter(|cache| remote_job.public_key.is_none() || cache.signature.is_some());
+	let mut request = remote_job.request(client, Method::GET, url)?;
+	if let Some(cache) = cache {
+		if let Some(etag) = &cache.etag {
+			request = request.header(IF_NONE_MATCH, etag);
+		}
+		if let Some(last_modified) = &cache.last_modified {
+			request = request.header(IF_MODIFIED_SINCE, last_modified);
+		}
+	}
+	let response = send(request, http, deadline)?;
+	if response.status() == StatusCode::NOT_MODIFIED && cache.is_some() {
+		return Ok(None);
+	}
+	let response = response
+		.error_for_status()
+		.context("failed to send request")?;
+	let header = |headers: &HeaderMap, name| {
+		headers
+			.get(name)
+			.and_then(|value| value.to_str().ok())
+			.map(ToOwned::to_owned)
+	};
+	let content_type = header(response.headers(), CONTENT_TYPE);
+	let etag = header(response.headers(), ETAG);
+	let last_modified = header(response.headers(), LAST_MODIFIED);
+	let body = read_body(response, http.max_body_size)?;
+	let signature = match remote_job.public_key {
+		Some(_) => {
+			// the signature is loaded from the same server as the file
+			let url = format!("{url}.sig");
+			let signature = send(
+				remote_job.request(client, Method::GET, &url)?,
+				http,
+				deadline
+			)
+			.and_then(|response| Ok(response.error_for_status()?))
+			.and_then(|response| read_body(response, http.max_body_size))
+			.with_context(|| format!("failed to load signature {url:?}"))?;
+			Some(signature)
+		},
+		None => None
+	};
+	Ok(Some(CacheEntry {
+		url: url.to_owned(),
+		checked: Local::now(),
+		etag,
+		last_modified,
+		body,
+		signature,
+		content_type
+	}))
+}
+
+/// an url and the result of requesting it
+type FetchResult = (String, anyhow::Result<Option<CacheEntry>>);
+
+/// Request the remote job from its url and mirrors, according to the mirror strategy.
+/// The url, which was loaded successfully, is logged.
+fn fetch_mirrors(
+	http: &HttpConfig,
+	client: &Client,
+	remote_job: &RemoteJob,
+	cache: Option<&CacheEntry>,
+	deadline: Instant
+) -> anyhow::Result<Option<CacheEntry>> {
+	if remote_job.mirrors.is_empty() {
+		return fetch(http, client, remote_job, &remote_job.url, cache, deadline);
+	}
+	let results: Box<dyn Iterator<Item = FetchResult> + '_> =
+		match remote_job.mirror_strategy {
+			MirrorStrategy::Ordered => Box::new(remote_job.urls().map(|url| {
+				let result = fetch(http, client, remote_job, url, cache, deadline);
+				(url.to_owned(), result)
+			})),
+			MirrorStrategy::Race => {
+				let (sender, receiver) = mpsc::channel();
+				for url in remote_job.urls() {
+					let sender = sender.clone();
+					let (http, client, remote_job) =
+						(http.clone(), client.clone(), remote_job.clone());
+					let (url, cache) = (url.to_owned(), cache.cloned());
+					// the threads are not joined, so slower urls do not delay the result
+					thread::spawn(move || {
+						let result = fetch(
+							&http,
+							&client,
+							&remote_job,
+							&url,
+							cache.as_ref(),
+							deadline
+						);
+						// the receiver is gone, if another url was faster
+						let _ = sender.send((url, result));
+					});
+				}
+				Box::new(receiver.into_iter())
+			}
+		};
+	let mut errors = Vec::new();
+	for (url, result) in results {
+		match result {
+			Ok(entry) => {
+				for err in &errors {
+					warn!("{err:?}");
+				}
+				info!(%url, "loaded remote job {:?} from {url:?}", remote_job.url);
+				return Ok(entry);
+			},
+			Err(err) => errors.push(err.context(format!("failed to load {url:?}")))
+		}
+	}
+	let errors: Vec<_> = errors.iter().map(|err| format!("{err:#}")).collect();
+	Err(anyhow!(
+		"all urls of the remote job failed:\n{}",
+		errors.join("\n")
+	))
+}
+
+/// Load the jobs of `remote_job`.
+/// All requests must be finished before `deadline`.
+/// The last successfully loaded version is cached at the data dir
+/// and used, if the server is unreachable or returns invalid jobs.
+pub fn get_remote_job(
+	config: &Config,
+	client: &Client,
+	remote_job: &RemoteJob,
+	deadline: Instant
+) -> anyhow::Result<(Tasks, NestedSources)> {
+	let path = cache_path(config, &remote_job.url);
+	let cache = load_cache(&path).unwrap_or_else(|err| {
+		warn!("{err:?}");
+		None
+	});
+	let loaded =
+		fetch_mirrors(&config.http, client, remote_job, cache.as_ref(), deadline)
+			.and_then(|entry| {
+				let entry = match (entry, &cache) {
+					(Some(entry), _) => entry,
+					(None, Some(cache)) => CacheEntry {
+						checked: Local::now(),
+						..cache.clone()
+					},
+					(None, None) => unreachable!("not modified without cached version")
+				};
+				let jobs = parse(config, remote_job, &entry)?;
+				Ok((jobs, entry))
+			});
+	match (loaded, cache) {
+		(Ok((jobs, entry)), _) => {
+			if let Err(err) = save_cache(&path, &entry).context("failed to save cache") {
+				warn!("{err:?}");
+			}
+			Ok(jobs)
+		},
+		(Err(err), Some(cache)) => {
+			let age = (Local::now() - cache.checked).to_std().unwrap_or_default();
+			if age > remote_job.max_stale {
+				return Err(err.context(format!(
+					"cached version from {} is too old",
+					cache.checked.format(TIME_FORMAT)
+				)));
+			}
+			warn!(
+				"{err:?}\nuse cached version from {}",
+				cache.checked.format(TIME_FORMAT)
+			);
+			parse(config, remote_job, &cache).context("failed to load cached version")
+		},
+		(Err(err), None) => Err(err)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn deserialize() {
+		#[derive(Debug, Deserialize)]
+		struct Wrapper {
+			remote_job: Vec<RemoteJob>
+		}
+		let value: Wrapper = toml::from_str(
+			r#"remote_job = [
+				"https://example.com/a.toml",
+				{ url = "https://example.com/b.toml", bearer_token_file = "token", headers = { X-Key = "value" } },
+				{ url = "https://example.com/c.toml", mirrors = "https://backup.example.com/c.toml", mirror_strategy = "race", report_url = "https://example.com/report" }
+			]"#
+		)
+		.unwrap();
+		assert_eq!(value.remote_job[0].url, "https://example.com/a.toml");
+		assert!(value.remote_job[0].bearer_token_file.is_none());
+		assert_eq!(value.remote_job[1].url, "https://example.com/b.toml");
+		assert_eq!(
+			value.remote_job[1].bearer_token_file,
+			Some(PathBuf::from("token"))
+		);
+		assert_eq!(value.remote_job[1].headers["X-Key"], "value");
+		assert_eq!(value.remote_job[1].mirror_strategy, MirrorStrategy::Ordered);
+		assert_eq!(value.remote_job[2].urls().collect::<Vec<_>>(), [
+			"https://example.com/c.toml",
+			"https://backup.example.com/c.toml"
+		]);
+		assert_eq!(value.remote_job[2].mirror_strategy, MirrorStrategy::Race);
+		assert_eq!(value.remote_job[0].report_url, None);
+		assert_eq!(
+			value.remote_job[2].report_url.as_deref(),
+			Some("https://example.com/report")
+		);
+
+		let err = toml::from_str::<Wrapper>(
+			r#"remote_job = [{ url = "https://example.com/a.toml", max_stael = "1d" }]"#
+		)
+		.unwrap_err();
+		assert!(err.message().contains("unknown field `max_stael`"), "{err}");
+	}
+
+	#[test]
+	fn format() {
+		assert_eq!(
+			Format::from_content_type("application/json; charset=utf-8"),
+			Some(Format::Json)
+		);
+		assert_eq!(
+			Format::from_content_type("application/vnd.jobs+yaml"),
+			Some(Format::Yaml)
+		);
+		assert_eq!(Format::from_content_type("text/plain"), None);
+		assert_eq!(
+			Format::from_url("https://example.com/jobs.yml?token=1"),
+			Some(Format::Yaml)
+		);
+		assert_eq!(Format::from_url("https://example.com/jobs"), None);
+
+		let source: TaskSource = Format::Json
+			.parse(r#"{ "download": [{ "name": "a", "url": "https://example.com", "profile": "local:audio" }] }"#)
+			.unwrap();
+		assert_eq!(source.download[0].profile, ["local:audio"]);
+	}
+
+	#[test]
+	fn signature() {
+		// test vector of minisign-verify, signing `test`
+		const PUBLIC_KEY: &str =
+			"RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+		const SIGNATURE: &str = "untrusted comment: signature from minisign secret key\n\
+			RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+			trusted comment: timestamp:1556193335\tfile:test\n\
+			y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==";
+
+		verify_signature(PUBLIC_KEY, "test", Some(SIGNATURE)).unwrap();
+		let err = verify_signature(PUBLIC_KEY, "Test", Some(SIGNATURE)).unwrap_err();
+		assert_eq!(err.to_string(), "invalid signature");
+		let err = verify_signature(PUBLIC_KEY, "test", None).unwrap_err();
+		assert_eq!(err.to_string(), "remote job is not signed");
+	}
+}
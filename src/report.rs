@@ -0,0 +1,120 @@
+use std::{
+	fs::{self, create_dir_all},
+	io::ErrorKind,
+	thread::sleep,
+	time::Duration
+};
+
+use anyhow::Context;
+use reqwest::{header::CONTENT_TYPE, Method};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+	config::Config,
+	remote::RemoteJob,
+	state::RunRecord,
+	tasks::{retry_delay, JobId}
+};
+
+/// time to wait before the first retry of a failed report
+const REPORT_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// summary of the runs of a remote job, posted to its report url
+#[derive(Serialize)]
+struct Report<'a> {
+	instance_id: String,
+	/// url of the remote job
+	remote_job: &'a str,
+	jobs: Vec<JobReport<'a>>
+}
+
+#[derive(Serialize)]
+struct JobReport<'a> {
+	#[serde(flatten)]
+	id: &'a JobId,
+	#[serde(flatten)]
+	run: &'a RunRecord,
+	duration_secs: u64
+}
+
+/// the configured instance id, or a random one which is created once and stored at the data dir
+fn instance_id(config: &Config) -> anyhow::Result<String> {
+	if let Some(id) = &config.instance_id {
+		return Ok(id.clone());
+	}
+	let path = config.data_path("instance_id");
+	match fs::read_to_string(&path) {
+		Ok(id) => return Ok(id.trim().to_owned()),
+		Err(err) if err.kind() == ErrorKind::NotFound => {},
+		Err(err) => return Err(err).with_context(|| format!("failed to read {path:?}"))
+	}
+	let id: String = (0 .. 16)
+		.map(|_| format!("{:02x}", fastrand::u8(..)))
+		.collect();
+	if let Some(dir) = path.parent() {
+		create_dir_all(dir).with_context(|| format!("failed to create dir {dir:?}"))?;
+	}
+	fs::write(&path, &id).with_context(|| format!("failed to write {path:?}"))?;
+	Ok(id)
+}
+
+/// Post the results of `runs` to the report url of `remote_job`.
+/// Failed requests are retried up to `report_retries` times.
+pub fn send_report(
+	config: &Config,
+	remote_job: &RemoteJob,
+	runs: &[(JobId, RunRecord)]
+) -> anyhow::Result<()> {
+	let Some(url) = &remote_job.report_url else {
+		return Ok(());
+	};
+	let report = Report {
+		instance_id: instance_id(config).context("failed to get instance id")?,
+		remote_job: &remote_job.url,
+		jobs: runs
+			.iter()
+			.map(|(id, run)| JobReport {
+				id,
+				run,
+				duration_secs: run.duration().as_secs()
+			})
+			.collect()
+	};
+	let body = serde_json::to_string(&report)?;
+	let client = config.http.client()?;
+	let mut attempt = 0;
+	loop {
+		let result = remote_job
+			.request(&client, Method::POST, url)
+			.and_then(|request| {
+				let response = request
+					.header(CONTENT_TYPE, "application/json")
+					.body(body.clone())
+					.send()?;
+				Ok(response.error_for_status()?)
+			})
+			.with_context(|| format!("failed to send report to {url:?}"));
+		let err = match result {
+			Ok(_) => {
+				info!(%url, "send report of {} runs to {url:?}", runs.len());
+				return Ok(());
+			},
+			Err(err) => err
+		};
+		if attempt >= remote_job.report_retries {
+			return Err(err);
+		}
+		let delay = retry_delay(REPORT_RETRY_BACKOFF, attempt);
+		attempt += 1;
+		warn!(
+			attempt,
+			retries = remote_job.report_retries,
+			delay_secs = delay.as_secs(),
+			"{err:#}; retry in {} ({attempt}/{})",
+			humantime::format_duration(Duration::from_secs(delay.as_secs())),
+			remote_job.report_retries
+		);
+		sleep(delay);
+	}
+}
@@ -0,0 +1,208 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{
+	de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+	Deserialize, Deserializer
+};
+
+use crate::{
+	config::{Config, Download, Profile},
+	state::{RunRecord, RunStatus}
+};
+
+/// When a job should run.
+#[derive(Clone, Debug)]
+pub enum Schedule {
+	/// Cron expression like `"0 4 * * Mon"`, evaluated in local time.
+	/// A leading seconds field and a trailing year field are also supported.
+	Cron(Box<cron::Schedule>),
+	/// Run every `every` time, like `{ every = "6h" }`, messured from start to start.
+	Every { every: Duration }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EveryTable {
+	#[serde(with = "humantime_serde")]
+	every: Duration
+}
+
+/// Chooses the variant by the type of the value,
+/// so the error of the cron parser or of the table is not replaced by a generic one.
+struct ScheduleVisitor;
+
+impl<'de> Visitor<'de> for ScheduleVisitor {
+	type Value = Schedule;
+
+	fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("a cron expression or a table like `{ every = \"6h\" }`")
+	}
+
+	fn visit_str<E: de::Error>(self, value: &str) -> Result<Schedule, E> {
+		parse_cron(value)
+			.map(|schedule| Schedule::Cron(Box::new(schedule)))
+			.map_err(|err| E::custom(format!("invalid cron expression {value:?}: {err}")))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Schedule, A::Error> {
+		let table = EveryTable::deserialize(MapAccessDeserializer::new(map))?;
+		Ok(Schedule::Every { every: table.every })
+	}
+}
+
+impl<'de> Deserialize<'de> for Schedule {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>
+	{
+		deserializer.deserialize_any(ScheduleVisitor)
+	}
+}
+
+impl Schedule {
+	/// next time the job should run, if it was started at `last_run`
+	pub fn next_after(&self, last_run: DateTime<Local>) -> Option<DateTime<Local>> {
+		match self {
+			Self::Cron(schedule) => schedule.after(&last_run).next(),
+			Self::Every { every } => {
+				last_run.checked_add_signed(TimeDelta::from_std(*every).ok()?)
+			},
+		}
+	}
+}
+
+/// Parse a cron expression.
+/// In addition to the format of the cron crate (with seconds field, Sunday is 1),
+/// the classic format with five fields (Sunday is 0 or 7) is also supported.
+fn parse_cron(expression: &str) -> Result<cron::Schedule, cron::error::Error> {
+	let fields: Vec<&str> = expression.split_whitespace().collect();
+	if let [minute, hour, day, month, day_of_week] = fields[..] {
+		let day_of_week = shift_day_of_week(day_of_week);
+		cron::Schedule::from_str(&format!(
+			"0 {minute} {hour} {day} {month} {day_of_week}"
+		))
+	} else {
+		cron::Schedule::from_str(expression)
+	}
+}
+
+/// Convert the day of week field of the classic format (Sunday is 0 or 7)
+/// to the one of the cron crate (Sunday is 1).
+/// Names of days are kept.
+fn shift_day_of_week(field: &str) -> String {
+	let shift = |day: &str| match day.parse::<u32>() {
+		Ok(0 | 7) => "1".to_owned(),
+		Ok(day) => (day + 1).to_string(),
+		Err(_) => day.to_owned()
+	};
+	let mut items = Vec::new();
+	for item in field.split(',') {
+		let (range, step) = match item.split_once('/') {
+			Some((range, step)) => (range, Some(step)),
+			None => (item, None)
+		};
+		let suffix = step.map(|step| format!("/{step}")).unwrap_or_default();
+		let (start, end) = match range.split_once('-') {
+			Some((start, end)) => (start, end),
+			// `START/STEP` runs until the end of the week
+			None if step.is_some() && range.parse::<u32>().is_ok() => (range, "7"),
+			None => {
+				items.push(format!("{}{suffix}", shift(range)));
+				continue;
+			}
+		};
+		let step_len = step.map_or(Some(1), |step| step.parse::<u32>().ok());
+		match (start.parse::<u32>(), end, step_len) {
+			// Sunday at the end of the range becomes 1, so it is moved to an own item
+			(Ok(start @ 0 ..= 6), "7", Some(step_len @ 1 ..)) => {
+				items.push(format!("{}-7{suffix}", start + 1));
+				if (7 - start) % step_len == 0 {
+					items.push("1".to_owned());
+				}
+			},
+			_ => items.push(format!("{}-{}{suffix}", shift(start), shift(end)))
+		}
+	}
+	items.join(",")
+}
+
+/// Return when the job should run next, if its last run was `last_run`.
+/// The schedule of the download is preferred over the schedule of the profile.
+/// Jobs without any schedule run every `interval` seconds.
+/// If the last run has failed, the job runs again after `retry_failed_after`,
+/// if this is earlier.
+/// Return `None` if the job will never run again.
+pub fn next_run(
+	config: &Config,
+	download: &Download,
+	profile: &Profile,
+	last_run: &RunRecord
+) -> Option<DateTime<Local>> {
+	let next_regular = match download.schedule.as_ref().or(profile.schedule.as_ref()) {
+		Some(schedule) => schedule.next_after(last_run.start),
+		None => last_run
+			.start
+			.checked_add_signed(TimeDelta::try_seconds(config.interval.try_into().ok()?)?)
+	};
+	let next_retry = config
+		.retry_failed_after
+		.filter(|_| last_run.status != RunStatus::Success)
+		.and_then(|retry_after| {
+			last_run
+				.end
+				.checked_add_signed(TimeDelta::from_std(retry_after).ok()?)
+		});
+	match (next_regular, next_retry) {
+		(Some(regular), Some(retry)) => Some(regular.min(retry)),
+		(regular, retry) => regular.or(retry)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use chrono::{Datelike, TimeZone, Timelike};
+
+	#[derive(Debug, Deserialize)]
+	struct Wrapper {
+		schedule: Schedule
+	}
+
+	#[test]
+	fn deserialize() {
+		let last_run = Local.with_ymd_and_hms(2024, 7, 1, 12, 30, 0).unwrap();
+
+		let cron: Wrapper = toml::from_str(r#"schedule = "0 * * * *""#).unwrap();
+		let next = cron.schedule.next_after(last_run).unwrap();
+		assert_eq!((next.hour(), next.minute()), (13, 0));
+
+		let every: Wrapper = toml::from_str(r#"schedule = { every = "6h" }"#).unwrap();
+		let next = every.schedule.next_after(last_run).unwrap();
+		assert_eq!((next.hour(), next.minute()), (18, 30));
+
+		for (expression, (day, hour, minute)) in [
+			("* * * * 1", (1, 12, 31)),
+			("0 4 * * 1", (8, 4, 0)),
+			("0 4 * * 0", (7, 4, 0)),
+			("0 4 * * 5-7", (5, 4, 0)),
+			("0 4 * * 3-7/2", (3, 4, 0)),
+			("0 4 * * 1-7/6", (7, 4, 0)),
+			("0 4 * * Sat,7", (6, 4, 0)),
+			("0 0 4 * * 2", (8, 4, 0))
+		] {
+			let cron: Wrapper =
+				toml::from_str(&format!("schedule = {expression:?}")).unwrap();
+			let next = cron.schedule.next_after(last_run).unwrap();
+			assert_eq!(
+				(next.day(), next.hour(), next.minute()),
+				(day, hour, minute)
+			);
+		}
+
+		let err = toml::from_str::<Wrapper>(r#"schedule = "0 25 * * *""#).unwrap_err();
+		assert!(err.to_string().contains("invalid cron expression"), "{err}");
+		let err = toml::from_str::<Wrapper>(r#"schedule = { evry = "6h" }"#).unwrap_err();
+		assert!(err.to_string().contains("unknown field `evry`"), "{err}");
+	}
+}
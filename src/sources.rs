@@ -0,0 +1,300 @@
+use std::{
+	collections::HashSet,
+	fs, mem,
+	path::{Path, PathBuf},
+	thread,
+	time::Instant
+};
+
+use anyhow::{anyhow, bail, Context};
+use glob::glob;
+use reqwest::blocking::Client;
+use tracing::{error, warn};
+
+use crate::{
+	config::{resolve_pattern, Config, NestedSources, ProfileLookup, TaskSource},
+	remote::{get_remote_job, Format, RemoteJob},
+	tasks::Tasks
+};
+
+/// maximum nesting depth of included files and remote jobs
+const MAX_DEPTH: usize = 8;
+
+/// a source of jobs, referenced by the config or by another source
+enum SourceKind {
+	Include(PathBuf),
+	Remote(Box<RemoteJob>)
+}
+
+/// a source, which still needs to be loaded
+struct PendingSource {
+	kind: SourceKind,
+	/// ids of the sources, which have referenced this source, outermost first
+	chain: Vec<String>
+}
+
+impl PendingSource {
+	/// path or url of the source
+	fn id(&self) -> String {
+		match &self.kind {
+			SourceKind::Include(path) => path.display().to_string(),
+			SourceKind::Remote(remote_job) => remote_job.url.clone()
+		}
+	}
+
+	/// name of the source, including the sources which have referenced it
+	fn label(&self) -> String {
+		let mut label = match &self.kind {
+			SourceKind::Include(path) => format!("jobs from {path:?}"),
+			SourceKind::Remote(remote_job) => {
+				format!("remote jobs from {:?}", remote_job.url)
+			}
+		};
+		for parent in self.chain.iter().rev() {
+			label.push_str(&format!(" via {parent:?}"));
+		}
+		label
+	}
+
+	fn check(&self) -> anyhow::Result<()> {
+		if self.chain.len() >= MAX_DEPTH {
+			bail!("maximum nesting depth of {MAX_DEPTH} exceeded");
+		}
+		let id = self.id();
+		if self.chain.contains(&id) {
+			bail!("cycle detected: {} -> {id}", self.chain.join(" -> "));
+		}
+		Ok(())
+	}
+
+	fn load(
+		&self,
+		config: &Config,
+		client: &anyhow::Result<Client>,
+		deadline: Instant
+	) -> anyhow::Result<(Tasks, NestedSources)> {
+		match &self.kind {
+			SourceKind::Include(path) => load_included(config, path),
+			SourceKind::Remote(remote_job) => {
+				let client = client.as_ref().map_err(|err| anyhow!("{err:#}"))?;
+				get_remote_job(config, client, remote_job, deadline)
+			}
+		}
+	}
+
+	/// sources referenced by this source
+	fn children(
+		&self,
+		nested: NestedSources,
+		errors: &mut Vec<anyhow::Error>
+	) -> Vec<PendingSource> {
+		let mut chain = self.chain.clone();
+		chain.push(self.id());
+		// relative paths of included files are relative to the file.
+		// Remote jobs can only reference other remote jobs without local paths.
+		let dir = match &self.kind {
+			SourceKind::Include(path) => path.parent(),
+			SourceKind::Remote(_) => None
+		};
+		let mut children = Vec::new();
+		for pattern in nested.include {
+			let pattern = match dir {
+				Some(dir) => resolve_pattern(dir, &pattern),
+				None => pattern
+			};
+			expand_include(&pattern, &chain, &mut children, errors);
+		}
+		for mut remote_job in nested.remote_job {
+			if let Some(dir) = dir {
+				remote_job.resolve_paths(dir);
+			}
+			children.push(PendingSource {
+				kind: SourceKind::Remote(Box::new(remote_job)),
+				chain: chain.clone()
+			});
+		}
+		children
+	}
+}
+
+/// add a pending source for each file matching `pattern`
+fn expand_include(
+	pattern: &str,
+	chain: &[String],
+	sources: &mut Vec<PendingSource>,
+	errors: &mut Vec<anyhow::Error>
+) {
+	let paths = match glob(pattern)
+		.with_context(|| format!("invalid include pattern {pattern:?}"))
+	{
+		Ok(value) => value,
+		Err(err) => {
+			error!("{err:?}");
+			errors.push(err);
+			return;
+		}
+	};
+	for path in paths {
+		match path.context("failed to read included file") {
+			Ok(path) => sources.push(PendingSource {
+				// the same file should have the same id, for the cycle detection
+				kind: SourceKind::Include(fs::canonicalize(&path).unwrap_or(path)),
+				chain: chain.to_vec()
+			}),
+			Err(err) => {
+				error!("{err:?}");
+				errors.push(err);
+			}
+		}
+	}
+}
+
+/// Add the jobs of `source` to `jobs`, if they could be loaded.
+/// Otherwise log the error, add it to `errors` and return `false`.
+fn add_jobs(
+	jobs: &mut Vec<(String, Tasks)>,
+	errors: &mut Vec<anyhow::Error>,
+	source: String,
+	tasks: anyhow::Result<Tasks>
+) -> bool {
+	// jobs of different sources must not share a download name or archive
+	let tasks = tasks
+		.and_then(|value| {
+			for (other_source, other) in jobs.iter() {
+				value
+					.check_conflicts(other)
+					.with_context(|| format!("conflict with {other_source}"))?;
+			}
+			Ok(value)
+		})
+		.with_context(|| format!("failed to load {source}"));
+	match tasks {
+		Ok(value) => {
+			jobs.push((source, value));
+			true
+		},
+		Err(err) => {
+			error!(%source, "{err:?}");
+			errors.push(err);
+			false
+		}
+	}
+}
+
+/// Load the jobs of an included file.
+/// Its downloads can use the profiles of the file and the profiles of the config.
+fn load_included(config: &Config, path: &Path) -> anyhow::Result<(Tasks, NestedSources)> {
+	let text =
+		fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+	let format = Format::from_path(path).unwrap_or(Format::Toml);
+	let mut source: TaskSource = format
+		.parse(&text)
+		.with_context(|| format!("failed to parse {format}"))?;
+	let nested = source.take_nested();
+	let tasks =
+		Tasks::with_local_profiles(source, &config.profile, ProfileLookup::RemoteFirst)?;
+	Ok((tasks, nested))
+}
+
+/// Load the local, the included and the remote jobs of the config, named by their source.
+/// Included files and remote jobs can reference further ones, which are loaded recursively.
+/// Jobs which can not be loaded are skipped and their errors are returned as second value.
+pub fn load_jobs(config: &Config) -> (Vec<(String, Tasks)>, Vec<anyhow::Error>) {
+	let mut errors = Vec::new();
+	let mut jobs = Vec::new();
+
+	add_jobs(
+		&mut jobs,
+		&mut errors,
+		"local jobs".to_owned(),
+		Tasks::try_from(config.local_task_source())
+	);
+
+	let mut pending = Vec::new();
+	for pattern in &config.include {
+		expand_include(pattern, &[], &mut pending, &mut errors);
+	}
+	pending.extend(config.remote_job.iter().map(|remote_job| PendingSource {
+		kind: SourceKind::Remote(Box::new(remote_job.clone())),
+		chain: Vec::new()
+	}));
+	if pending.is_empty() {
+		return (jobs, errors);
+	}
+
+	let client = &config.http.client();
+	// sources of the same nesting level are loaded at the same time,
+	// so a slow server does only delay the others up to the total timeout
+	let deadline = Instant::now() + config.http.total_timeout;
+	let mut loaded = HashSet::new();
+	while !pending.is_empty() {
+		let mut level = Vec::new();
+		for source in mem::take(&mut pending) {
+			if let Err(err) = source.check() {
+				add_jobs(&mut jobs, &mut errors, source.label(), Err(err));
+			} else if !loaded.insert(source.id()) {
+				warn!("skip {}, because it was already loaded", source.label());
+			} else {
+				level.push(source);
+			}
+		}
+
+		let results: Vec<_> = thread::scope(|scope| {
+			let handles: Vec<_> = level
+				.iter()
+				.map(|source| scope.spawn(move || source.load(config, client, deadline)))
+				.collect();
+			handles
+				.into_iter()
+				.map(|handle| handle.join().unwrap())
+				.collect()
+		});
+		for (source, result) in level.iter().zip(results) {
+			match result {
+				Ok((tasks, nested)) => {
+					if add_jobs(&mut jobs, &mut errors, source.label(), Ok(tasks)) {
+						pending.extend(source.children(nested, &mut errors));
+					}
+				},
+				Err(err) => {
+					add_jobs(&mut jobs, &mut errors, source.label(), Err(err));
+				}
+			}
+		}
+	}
+	(jobs, errors)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn include(path: &str, chain: &[&str]) -> PendingSource {
+		PendingSource {
+			kind: SourceKind::Include(PathBuf::from(path)),
+			chain: chain.iter().map(|id| (*id).to_owned()).collect()
+		}
+	}
+
+	#[test]
+	fn check() {
+		include("a.toml", &[]).check().unwrap();
+		include("c.toml", &["a.toml", "b.toml"]).check().unwrap();
+		let err = include("a.toml", &["a.toml", "b.toml"])
+			.check()
+			.unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"cycle detected: a.toml -> b.toml -> a.toml"
+		);
+
+		let chain: Vec<String> = (0 .. MAX_DEPTH).map(|i| format!("{i}.toml")).collect();
+		let chain: Vec<&str> = chain.iter().map(String::as_str).collect();
+		include("x.toml", &chain[1 ..]).check().unwrap();
+		let err = include("x.toml", &chain).check().unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			format!("maximum nesting depth of {MAX_DEPTH} exceeded")
+		);
+	}
+}
@@ -0,0 +1,176 @@
+use std::{
+	collections::HashMap,
+	fmt::{self, Display},
+	fs::{self, create_dir_all},
+	io::ErrorKind,
+	path::PathBuf,
+	sync::{Mutex, MutexGuard}
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+	config::Config,
+	tasks::{ExitStatusError, JobId, TimeoutError}
+};
+
+/// Maximum number of runs, which are stored per job.
+/// Older runs are removed.
+const MAX_RUNS_PER_JOB: usize = 100;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+	Success,
+	Failed,
+	TimedOut
+}
+
+impl Display for RunStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Success => "success",
+			Self::Failed => "failed",
+			Self::TimedOut => "timed out"
+		})
+	}
+}
+
+/// a single run of a job
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunRecord {
+	pub start: DateTime<Local>,
+	pub end: DateTime<Local>,
+	pub status: RunStatus,
+	/// exit code of yt-dlp, if it was executed and has exit normally
+	pub exit_code: Option<i32>,
+	/// error message, if the job has failed
+	pub error: Option<String>
+}
+
+impl RunRecord {
+	pub fn new(
+		start: DateTime<Local>,
+		end: DateTime<Local>,
+		result: &anyhow::Result<()>
+	) -> Self {
+		let (status, exit_code, error) = match result {
+			Ok(()) => (RunStatus::Success, Some(0), None),
+			Err(err) if err.downcast_ref::<TimeoutError>().is_some() => {
+				(RunStatus::TimedOut, None, Some(format!("{err:#}")))
+			},
+			Err(err) => (
+				RunStatus::Failed,
+				err.downcast_ref::<ExitStatusError>()
+					.and_then(|err| err.0.code()),
+				Some(format!("{err:#}"))
+			)
+		};
+		Self {
+			start,
+			end,
+			status,
+			exit_code,
+			error
+		}
+	}
+
+	pub fn duration(&self) -> std::time::Duration {
+		(self.end - self.start).to_std().unwrap_or_default()
+	}
+}
+
+#[derive(Deserialize, Serialize)]
+struct JobHistory {
+	#[serde(flatten)]
+	id: JobId,
+	runs: Vec<RunRecord>
+}
+
+/// content of the state file
+#[derive(Default, Deserialize, Serialize)]
+struct StateFile {
+	jobs: Vec<JobHistory>
+}
+
+/// Persistent history of all job runs,
+/// stored as json file at the data dir.
+pub struct StateStore {
+	path: PathBuf,
+	/// runs per job, oldest first
+	jobs: Mutex<HashMap<JobId, Vec<RunRecord>>>
+}
+
+impl StateStore {
+	/// load the state of the data dir of `config`, or create an empty state if none exists yet
+	pub fn open(config: &Config) -> anyhow::Result<Self> {
+		let path = config.data_path("state.json");
+		let state: StateFile = match fs::read_to_string(&path) {
+			Ok(value) => serde_json::from_str(&value)
+				.with_context(|| format!("failed to parse state file {path:?}"))?,
+			Err(err) if err.kind() == ErrorKind::NotFound => StateFile::default(),
+			Err(err) => {
+				return Err(err)
+					.with_context(|| format!("failed to read state file {path:?}"))
+			},
+		};
+		let jobs = state
+			.jobs
+			.into_iter()
+			.map(|history| (history.id, history.runs))
+			.collect();
+		Ok(Self {
+			path,
+			jobs: Mutex::new(jobs)
+		})
+	}
+
+	fn save(&self, jobs: &HashMap<JobId, Vec<RunRecord>>) -> anyhow::Result<()> {
+		let state = StateFile {
+			jobs: jobs
+				.iter()
+				.map(|(id, runs)| JobHistory {
+					id: id.clone(),
+					runs: runs.clone()
+				})
+				.collect()
+		};
+		if let Some(dir) = self.path.parent() {
+			create_dir_all(dir)
+				.with_context(|| format!("failed to create dir {dir:?}"))?;
+		}
+		// write to a temporary file first, so the state does not get corrupted
+		// if the program is killed while writing
+		let tmp_path = self.path.with_extension("json.tmp");
+		fs::write(&tmp_path, serde_json::to_string_pretty(&state)?)
+			.with_context(|| format!("failed to write {tmp_path:?}"))?;
+		fs::rename(&tmp_path, &self.path)
+			.with_context(|| format!("failed to move {tmp_path:?} to {:?}", self.path))
+	}
+
+	/// add a run to the history of the job and save the state
+	pub fn record(&self, id: JobId, record: RunRecord) {
+		let mut jobs = self.jobs.lock().unwrap();
+		let runs = jobs.entry(id).or_default();
+		runs.push(record);
+		if runs.len() > MAX_RUNS_PER_JOB {
+			runs.drain(.. runs.len() - MAX_RUNS_PER_JOB);
+		}
+		if let Err(err) = self.save(&jobs).context("failed to save state") {
+			error!("{err:?}");
+		}
+	}
+
+	/// the last run of the job
+	pub fn last_run(&self, id: &JobId) -> Option<RunRecord> {
+		self.jobs.lock().unwrap().get(id)?.last().cloned()
+	}
+
+	/// runs of all jobs, oldest first
+	pub fn jobs(&self) -> MutexGuard<'_, HashMap<JobId, Vec<RunRecord>>> {
+		self.jobs.lock().unwrap()
+	}
+}
@@ -0,0 +1,742 @@
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	fmt::{self, Display},
+	fs::{self, create_dir_all},
+	path::{Path, PathBuf},
+	process::{Child, Command, ExitStatus},
+	sync::{Condvar, Mutex},
+	thread::{self, sleep},
+	time::{Duration, Instant}
+};
+
+use anyhow::{bail, Context};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, info_span, warn};
+
+use crate::{
+	config::{Config, Download, Profile, ProfileLookup, TaskSource},
+	job_log::create_log_file,
+	remote::RemoteJob,
+	report::send_report,
+	state::{RunRecord, StateStore}
+};
+
+/// yt-dlp has exit with an unsuccessful status
+#[derive(Debug)]
+pub struct ExitStatusError(pub ExitStatus);
+
+impl Display for ExitStatusError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "command exit with error status {}", self.0)
+	}
+}
+
+impl std::error::Error for ExitStatusError {}
+
+/// yt-dlp was still running after the timeout and has been terminated
+#[derive(Debug)]
+pub struct TimeoutError(pub Duration);
+
+impl Display for TimeoutError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"command has timed out after {}",
+			humantime::format_duration(self.0)
+		)
+	}
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// how often it is checked, if a process with timeout has exit
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// prefix of profile names, which reference a profile of the local config from a remote job
+pub const LOCAL_PROFILE_PREFIX: &str = "local:";
+
+/// Maximum length of download and profile names,
+/// so the file names build from them stay below the usual limit of 255 bytes.
+const MAX_NAME_LEN: usize = 100;
+
+/// identifier of a download/profile combination
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct JobId {
+	/// namespace of the remote job, `None` for local jobs
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub namespace: Option<String>,
+	pub download: String,
+	pub profile: String
+}
+
+impl JobId {
+	pub fn new(download: &Download, profile: &Profile) -> Self {
+		Self {
+			namespace: download.namespace.clone(),
+			download: download.name.clone(),
+			profile: profile.name.clone()
+		}
+	}
+
+	/// Name used for the archive and log files of the job.
+	/// Jobs of remote jobs are stored at a subdirectory named by the namespace.
+	pub fn file_name(&self) -> String {
+		match &self.namespace {
+			Some(namespace) => format!("{namespace}/{}-{}", self.download, self.profile),
+			None => format!("{}-{}", self.download, self.profile)
+		}
+	}
+}
+
+impl Display for JobId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if let Some(namespace) = &self.namespace {
+			write!(f, "{namespace}/")?;
+		}
+		write!(f, "{:?} with profile {:?}", self.download, self.profile)
+	}
+}
+
+/// Check if `name` can be used as part of a file name.
+/// Only alphanumeric characters, `-`, `_`, `.` and spaces are allowed
+/// and the name must not start with a `.` or space.
+pub fn check_name(name: &str) -> anyhow::Result<()> {
+	if name.is_empty() {
+		bail!("name must not be empty");
+	}
+	if name.len() > MAX_NAME_LEN {
+		bail!("name must not be longer than {MAX_NAME_LEN} bytes");
+	}
+	if name.starts_with(['.', ' ']) {
+		bail!("name must not start with a dot or space");
+	}
+	if let Some(c) = name
+		.chars()
+		.find(|c| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ')))
+	{
+		bail!("name must not contain {c:?}");
+	}
+	Ok(())
+}
+
+pub struct Tasks {
+	pub profiles: HashMap<String, Profile>,
+	pub download: Vec<Download>,
+	/// remote job, to whose report url the results of the runs are send
+	pub report_to: Option<RemoteJob>
+}
+
+/// download/profile combination and the index of the source it belongs to
+type Job<'a> = (usize, &'a Download, &'a Profile);
+
+/// Download/profile combinations of all sources, which are waiting to be processed.
+/// Can be shared between multiple worker threads.
+struct JobQueue<'a> {
+	state: Mutex<JobQueueState<'a>>,
+	job_finished: Condvar
+}
+
+struct JobQueueState<'a> {
+	pending: VecDeque<Job<'a>>,
+	/// count of running jobs per source index and profile name
+	running: HashMap<(usize, &'a str), usize>
+}
+
+impl<'a> JobQueue<'a> {
+	fn new(jobs: VecDeque<Job<'a>>) -> Self {
+		Self {
+			state: Mutex::new(JobQueueState {
+				pending: jobs,
+				running: HashMap::new()
+			}),
+			job_finished: Condvar::new()
+		}
+	}
+
+	/// Take the next job, whose profile has not reached its `max_parallel` limit yet.
+	/// Block until such a job is available.
+	/// Return `None` if all jobs have been taken.
+	fn next(&self) -> Option<Job<'a>> {
+		let mut state = self.state.lock().unwrap();
+		loop {
+			if state.pending.is_empty() {
+				return None;
+			}
+			let running = &state.running;
+			let index = state.pending.iter().position(|(source, _, profile)| {
+				profile.max_parallel.is_none_or(|max| {
+					let count = running.get(&(*source, profile.name.as_str()));
+					count.copied().unwrap_or(0) < max.get()
+				})
+			});
+			if let Some(index) = index {
+				let job = state.pending.remove(index).unwrap();
+				*state
+					.running
+					.entry((job.0, job.2.name.as_str()))
+					.or_default() += 1;
+				return Some(job);
+			}
+			state = self.job_finished.wait(state).unwrap();
+		}
+	}
+
+	/// mark a job taken by [Self::next] as finished
+	fn finish(&self, source: usize, profile: &'a Profile) {
+		let mut state = self.state.lock().unwrap();
+		if let Some(count) = state.running.get_mut(&(source, profile.name.as_str())) {
+			*count -= 1;
+		}
+		self.job_finished.notify_all();
+	}
+}
+
+impl Tasks {
+	/// all download/profile combinations
+	pub fn jobs(&self) -> impl Iterator<Item = (&Download, &Profile)> {
+		self.download.iter().flat_map(move |download| {
+			download.profile.iter().map(move |profile_name| {
+				(download, self.profiles.get(profile_name).unwrap())
+			})
+		})
+	}
+
+	/// Fail if `self` and `other` contain a download with the same name
+	/// or jobs using the same archive.
+	pub fn check_conflicts(&self, other: &Tasks) -> anyhow::Result<()> {
+		for download in &self.download {
+			if other.download.iter().any(|value| {
+				value.name == download.name && value.namespace == download.namespace
+			}) {
+				bail!("duplicate download name {:?}", download.name);
+			}
+		}
+		let file_names: HashSet<_> = other
+			.jobs()
+			.map(|(download, profile)| JobId::new(download, profile).file_name())
+			.collect();
+		for (download, profile) in self.jobs() {
+			let file_name = JobId::new(download, profile).file_name();
+			if file_names.contains(&file_name) {
+				bail!(
+					"download {:?} with profile {:?} would use the same archive {file_name:?} as another job",
+					download.name,
+					profile.name
+				);
+			}
+		}
+		Ok(())
+	}
+
+	/// Move all downloads into `namespace`,
+	/// so they do not share archives with downloads of other sources.
+	pub fn set_namespace(&mut self, namespace: &str) {
+		for download in &mut self.download {
+			download.namespace = Some(namespace.to_owned());
+		}
+	}
+
+	/// get the download and the profile with the given names.
+	/// The profile does not need to be used by the download.
+	pub fn get(
+		&self,
+		download_name: &str,
+		profile_name: &str
+	) -> Option<(&Download, &Profile)> {
+		let download = self
+			.download
+			.iter()
+			.find(|download| download.name == download_name)?;
+		let profile = self.profiles.get(profile_name)?;
+		Some((download, profile))
+	}
+}
+
+impl TryFrom<TaskSource> for Tasks {
+	type Error = anyhow::Error;
+
+	fn try_from(value: TaskSource) -> Result<Self, Self::Error> {
+		Self::with_local_profiles(value, &[], ProfileLookup::Remote)
+	}
+}
+
+impl Tasks {
+	/// Create the tasks of a remote job.
+	/// Its downloads can reference `local_profiles` with the `local:` prefix
+	/// or without prefix according to `lookup`.
+	pub fn with_local_profiles(
+		mut value: TaskSource,
+		local_profiles: &[Profile],
+		lookup: ProfileLookup
+	) -> anyhow::Result<Self> {
+		let mut hash_profiles: HashMap<String, Profile> =
+			HashMap::with_capacity(value.profile.len());
+
+		// convert profile to hashmap
+		for profile in value.profile {
+			let profile_name = profile.name.clone();
+			check_name(&profile_name)
+				.with_context(|| format!("invalid profile name {profile_name:?}"))?;
+			if hash_profiles
+				.insert(profile_name.clone(), profile)
+				.is_some()
+			{
+				bail!("duplicate profile name {} at config", profile_name)
+			}
+		}
+
+		let mut download_names = HashSet::with_capacity(value.download.len());
+		for download in &value.download {
+			check_name(&download.name)
+				.with_context(|| format!("invalid download name {:?}", download.name))?;
+			if !download_names.insert(download.name.as_str()) {
+				bail!("duplicate download name {:?} at config", download.name)
+			}
+		}
+
+		// resolve local profiles and check if all profile refs are valid
+		let mut file_names = HashSet::new();
+		for download in &mut value.download {
+			for profile_name in &mut download.profile {
+				let local_name = match profile_name.strip_prefix(LOCAL_PROFILE_PREFIX) {
+					Some(name) => Some(name.to_owned()),
+					None => {
+						let remote = hash_profiles.contains_key(profile_name.as_str());
+						let local = local_profiles
+							.iter()
+							.any(|profile| profile.name == *profile_name);
+						match lookup {
+							ProfileLookup::Remote => None,
+							ProfileLookup::RemoteFirst => {
+								(!remote && local).then(|| profile_name.clone())
+							},
+							ProfileLookup::LocalFirst => {
+								local.then(|| profile_name.clone())
+							},
+						}
+					}
+				};
+				// local profiles are stored with prefix, so they can not collide with remote ones
+				if let Some(local_name) = local_name {
+					let profile = local_profiles
+						.iter()
+						.find(|profile| profile.name == local_name)
+						.with_context(|| {
+							format!(
+								"can not find local profile {local_name:?} at download {:?}",
+								download.name
+							)
+						})?;
+					*profile_name = format!("{LOCAL_PROFILE_PREFIX}{local_name}");
+					hash_profiles
+						.entry(profile_name.clone())
+						.or_insert_with(|| profile.clone());
+				}
+
+				let profile = hash_profiles.get(profile_name).with_context(|| {
+					format!(
+						"can not find profile {:?} at download {:?}",
+						profile_name, download.name
+					)
+				})?;
+				// names like `a-b` + `c` and `a` + `b-c` would share the same archive
+				let file_name = format!("{}-{}", download.name, profile.name);
+				if !file_names.insert(file_name.clone()) {
+					bail!(
+						"download {:?} with profile {:?} would use the same archive {file_name:?} as another job",
+						download.name,
+						profile_name
+					);
+				}
+			}
+		}
+		Ok(Self {
+			profiles: hash_profiles,
+			download: value.download,
+			report_to: None
+		})
+	}
+}
+
+/// run all task and download all videos with associated settings.
+/// Return `false` if any download has failed.
+pub fn run_all(config: &Config, state: &StateStore, sources: &[(String, Tasks)]) -> bool {
+	run_filtered(config, state, sources, |_, _| true)
+}
+
+/// Run all download/profile combinations of `sources` for which `filter` returns `true`.
+/// The jobs of all sources share one queue,
+/// so at most `max_parallel` downloads are running at the same time.
+/// Each run is recorded at `state` and reported to the report url of its source ([Tasks::report_to]).
+/// Return `false` if any download has failed.
+pub fn run_filtered<F>(
+	config: &Config,
+	state: &StateStore,
+	sources: &[(String, Tasks)],
+	mut filter: F
+) -> bool
+where
+	F: FnMut(&Download, &Profile) -> bool
+{
+	let mut jobs = VecDeque::new();
+	for (index, (source, tasks)) in sources.iter().enumerate() {
+		let len = jobs.len();
+		jobs.extend(
+			tasks
+				.jobs()
+				.filter(|(download, profile)| filter(download, profile))
+				.map(|(download, profile)| (index, download, profile))
+		);
+		if jobs.len() > len {
+			info!(%source, "run {source}");
+		}
+	}
+	let worker_count = config.max_parallel.get().min(jobs.len());
+	let queue = &JobQueue::new(jobs);
+
+	// download
+	let results: Vec<(Vec<anyhow::Error>, Vec<_>)> = thread::scope(|scope| {
+		let workers: Vec<_> = (0 .. worker_count)
+			.map(|_| {
+				scope.spawn(move || {
+					let mut errors = Vec::new();
+					let mut runs = Vec::new();
+					while let Some((source, download_config, profile)) = queue.next() {
+						let span = info_span!(
+							"job",
+							namespace = download_config.namespace.as_deref(),
+							download = %download_config.name,
+							profile = %profile.name
+						);
+						let _entered = span.enter();
+						let start = Local::now();
+						let res = download_with_retries(config, download_config, profile)
+							.with_context(|| {
+								format!(
+									"Falied to process {:?} with profile {:?}",
+									download_config.name, profile.name
+								)
+							});
+						queue.finish(source, profile);
+						let record = RunRecord::new(start, Local::now(), &res);
+						match res {
+							Ok(()) => info!(
+								duration_secs = record.duration().as_secs(),
+								exit_code = record.exit_code,
+								"Downloaded {:?} with profile {:?} in {}",
+								download_config.name,
+								profile.name,
+								humantime::format_duration(Duration::from_secs(
+									record.duration().as_secs()
+								))
+							),
+							Err(err) => {
+								error!(
+									duration_secs = record.duration().as_secs(),
+									status = %record.status,
+									exit_code = record.exit_code,
+									"{err:#}"
+								);
+								errors.push(err);
+							}
+						};
+						let id = JobId::new(download_config, profile);
+						runs.push((source, id.clone(), record.clone()));
+						state.record(id, record);
+					}
+					(errors, runs)
+				})
+			})
+			.collect();
+		workers
+			.into_iter()
+			.map(|worker| worker.join().unwrap())
+			.collect()
+	});
+	let (errors, runs): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+	let errors: Vec<_> = errors.into_iter().flatten().collect();
+	let runs: Vec<_> = runs.into_iter().flatten().collect();
+
+	for (index, (_, tasks)) in sources.iter().enumerate() {
+		let Some(remote_job) = &tasks.report_to else {
+			continue;
+		};
+		let runs: Vec<_> = runs
+			.iter()
+			.filter(|(source, ..)| *source == index)
+			.map(|(_, id, record)| (id.clone(), record.clone()))
+			.collect();
+		if !runs.is_empty() {
+			if let Err(err) = send_report(config, remote_job, &runs) {
+				error!("{err:?}");
+			}
+		}
+	}
+
+	// print error again as summary
+	// otherwise the user will not be able to find it at wall of text
+	let success = errors.is_empty();
+	for error in errors {
+		error!("{error:?}\n");
+	}
+	success
+}
+
+pub fn archive_path(config: &Config, download: &Download, profile: &Profile) -> PathBuf {
+	config
+		.data_path("archives")
+		.join(format!("{}.txt", JobId::new(download, profile).file_name()))
+}
+
+/// create the yt-dlp command to download `download` with `profile`
+pub fn build_command(config: &Config, download: &Download, profile: &Profile) -> Command {
+	let mut cmd = Command::new(&config.bin_name);
+	if let Some(data_dir) = &config.data_dir {
+		cmd.current_dir(data_dir);
+	}
+	if profile.archive {
+		cmd.arg("--download-archive");
+		cmd.arg(archive_path(config, download, profile));
+	}
+	cmd.args(&profile.args);
+	// urls must not be parsed as options
+	cmd.arg("--");
+	cmd.args(&download.url);
+	cmd
+}
+
+/// Exponential backoff with jitter:
+/// wait between the half and the full of `backoff * 2^attempt`.
+pub fn retry_delay(backoff: Duration, attempt: u32) -> Duration {
+	let delay = backoff.saturating_mul(2_u32.saturating_pow(attempt));
+	Duration::try_from_secs_f64(delay.as_secs_f64() * (0.5 + fastrand::f64() * 0.5))
+		.unwrap_or(delay)
+}
+
+/// run [download] and retry it according to the retry settings of the profile
+fn download_with_retries(
+	config: &Config,
+	download_config: &Download,
+	profile: &Profile
+) -> anyhow::Result<()> {
+	let mut attempt = 0;
+	loop {
+		let err = match download(config, download_config, profile) {
+			Ok(()) => return Ok(()),
+			Err(err) => err
+		};
+		let retry = attempt < profile.retries
+			&& (profile.retry_on_exit_codes.is_empty()
+				|| err
+					.downcast_ref::<ExitStatusError>()
+					.and_then(|err| err.0.code())
+					.is_some_and(|code| profile.retry_on_exit_codes.contains(&code)));
+		if !retry {
+			return Err(err);
+		}
+		let delay = retry_delay(profile.retry_backoff, attempt);
+		attempt += 1;
+		warn!(
+			attempt,
+			retries = profile.retries,
+			delay_secs = delay.as_secs(),
+			"{err:#}; retry {:?} with profile {:?} in {} ({attempt}/{})",
+			download_config.name,
+			profile.name,
+			humantime::format_duration(Duration::from_secs(delay.as_secs())),
+			profile.retries
+		);
+		sleep(delay);
+	}
+}
+
+/// Archives of remote jobs were stored without namespace before.
+/// If the namespaced archive does not exist yet, the old one is copied to it,
+/// so already downloaded videos are not downloaded again.
+/// It is not moved, because local downloads or other remote jobs with the same name might use it too.
+fn migrate_archive(
+	config: &Config,
+	download: &Download,
+	profile: &Profile,
+	archive: &Path
+) -> anyhow::Result<()> {
+	if download.namespace.is_none() || archive.exists() {
+		return Ok(());
+	}
+	let old_archive = config
+		.data_path("archives")
+		.join(format!("{}-{}.txt", download.name, profile.name));
+	if !old_archive.exists() {
+		return Ok(());
+	}
+	info!("copy archive {old_archive:?} without namespace to {archive:?}");
+	fs::copy(&old_archive, archive).with_context(|| {
+		format!("failed to copy archive {old_archive:?} to {archive:?}")
+	})?;
+	Ok(())
+}
+
+/// Run yt-dlp for the download/profile combination.
+/// The output of yt-dlp is written to a log file.
+fn download(
+	config: &Config,
+	download: &Download,
+	profile: &Profile
+) -> anyhow::Result<()> {
+	debug!(
+		url_count = download.url.len(),
+		"Download {:?} with profile {:?}", download.name, profile.name
+	);
+	if profile.archive {
+		let archive = archive_path(config, download, profile);
+		if let Some(archive_dir) = archive.parent() {
+			create_dir_all(archive_dir)
+				.with_context(|| format!("failed to create dir {archive_dir:?}"))?;
+		}
+		migrate_archive(config, download, profile, &archive)?;
+	}
+	let mut cmd = build_command(config, download, profile);
+	let timeout = download.timeout.or(profile.timeout);
+	let (log_path, log_file) = create_log_file(config, download, profile, &cmd)
+		.context("failed to create log file")?;
+	cmd.stdout(
+		log_file
+			.try_clone()
+			.with_context(|| format!("failed to clone file handle of {log_path:?}"))?
+	);
+	cmd.stderr(log_file);
+	// use an own process group, so the whole group including ffmpeg can be killed on timeout
+	#[cfg(unix)]
+	if timeout.is_some() {
+		use std::os::unix::process::CommandExt;
+		cmd.process_group(0);
+	}
+	debug!(command = ?cmd, log_file = ?log_path, "run yt-dlp");
+	let mut child = cmd.spawn().with_context(|| "failed to execute command")?;
+	let status = wait_with_timeout(&mut child, timeout, config.timeout_grace_period)
+		.with_context(|| format!("output of yt-dlp: {log_path:?}"))?;
+	if !status.success() {
+		return Err(ExitStatusError(status))
+			.with_context(|| format!("output of yt-dlp: {log_path:?}"));
+	}
+	Ok(())
+}
+
+/// Wait until the child has exit.
+/// If it is still running after `timeout`, it is terminated and a [TimeoutError] is returned.
+fn wait_with_timeout(
+	child: &mut Child,
+	timeout: Option<Duration>,
+	grace_period: Duration
+) -> anyhow::Result<ExitStatus> {
+	let Some(timeout) = timeout else {
+		return child.wait().with_context(|| "failed to wait for command");
+	};
+	let deadline = Instant::now() + timeout;
+	while Instant::now() < deadline {
+		if let Some(status) = child
+			.try_wait()
+			.with_context(|| "failed to wait for command")?
+		{
+			return Ok(status);
+		}
+		sleep(POLL_INTERVAL);
+	}
+	warn!(
+		timeout_secs = timeout.as_secs(),
+		"command is still running after {}, terminate it",
+		humantime::format_duration(timeout)
+	);
+	terminate(child, grace_period)?;
+	bail!(TimeoutError(timeout))
+}
+
+/// Send SIGTERM to the process group of the child
+/// and SIGKILL if it is still running after `grace_period`.
+#[cfg(unix)]
+fn terminate(child: &mut Child, grace_period: Duration) -> anyhow::Result<()> {
+	let process_group =
+		libc::pid_t::try_from(child.id()).with_context(|| "invalid process id")?;
+	let signal_group = |signal| {
+		// SAFETY: only sends a signal to the process group, which was created for the child.
+		// The kernel does not reuse the group id, while any member of the group is alive.
+		// If all members have exit, the call fails with ESRCH, which is harmless.
+		unsafe { libc::kill(-process_group, signal) }
+	};
+	signal_group(libc::SIGTERM);
+	let deadline = Instant::now() + grace_period;
+	while Instant::now() < deadline {
+		if child
+			.try_wait()
+			.with_context(|| "failed to wait for command")?
+			.is_some()
+		{
+			break;
+		}
+		sleep(POLL_INTERVAL);
+	}
+	// the group leader may have exit, while other processes of the group (like ffmpeg) are still running
+	signal_group(libc::SIGKILL);
+	child.wait().with_context(|| "failed to wait for command")?;
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child, _grace_period: Duration) -> anyhow::Result<()> {
+	child.kill().with_context(|| "failed to kill command")?;
+	child.wait().with_context(|| "failed to wait for command")?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn name() {
+		for name in [
+			"Rickroll",
+			"audio-best",
+			"Never Gonna_Give.You.Up",
+			"Ünïcödé"
+		] {
+			check_name(name).unwrap();
+		}
+		for name in [
+			"", ".", "..", "../x", "a/b", "a\\b", " a", "a\0b", ".hidden"
+		] {
+			check_name(name).unwrap_err();
+		}
+	}
+
+	#[test]
+	fn local_profiles() {
+		let local: TaskSource = toml::from_str(
+			r#"
+			download = []
+			profile = [{ name = "audio", args = ["-x"] }, { name = "video", args = [] }]
+			"#
+		)
+		.unwrap();
+		let remote: TaskSource = toml::from_str(
+			r#"
+			profile = [{ name = "video", args = ["-f", "best"] }]
+			download = [{ name = "remote", url = "https://example.com", profile = ["local:audio", "video"] }]
+			"#
+		)
+		.unwrap();
+		let profile_args = |lookup| {
+			let tasks =
+				Tasks::with_local_profiles(remote.clone(), &local.profile, lookup)
+					.unwrap();
+			tasks
+				.jobs()
+				.map(|(_, profile)| profile.args.join(" "))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(profile_args(ProfileLookup::Remote), ["-x", "-f best"]);
+		assert_eq!(profile_args(ProfileLookup::LocalFirst), ["-x", ""]);
+		assert!(Tasks::try_from(remote).is_err());
+	}
+}
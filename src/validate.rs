@@ -0,0 +1,488 @@
+use std::{
+	collections::{hash_map::Entry, HashMap, HashSet},
+	fmt::{self, Display},
+	mem,
+	ops::Range,
+	path::Path
+};
+
+use minisign_verify::PublicKey;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize};
+use toml_edit::ImDocument;
+
+use crate::{
+	config::{Config, Download, Profile},
+	remote::RemoteJob,
+	tasks::check_name
+};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+	Warning,
+	Error
+}
+
+impl Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Warning => "warning",
+			Self::Error => "error"
+		})
+	}
+}
+
+/// a single problem of the config
+#[derive(Debug)]
+pub struct Diagnostic {
+	pub severity: Severity,
+	pub message: String,
+	/// byte range of the config file, where the problem is located
+	pub span: Option<Range<usize>>
+}
+
+impl Diagnostic {
+	/// Format the diagnostic as `FILE:LINE:COLUMN: SEVERITY: MESSAGE`.
+	/// `text` must be the content of `file`.
+	pub fn format(&self, file: &Path, text: &str) -> String {
+		match &self.span {
+			Some(span) => {
+				let (line, column) = line_column(text, span.start);
+				format!(
+					"{}:{line}:{column}: {}: {}",
+					file.display(),
+					self.severity,
+					self.message
+				)
+			},
+			None => format!("{}: {}: {}", file.display(), self.severity, self.message)
+		}
+	}
+}
+
+/// one based line and column of the byte `offset` of `text`
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+	let before = text.get(.. offset).unwrap_or(text);
+	let line = before.matches('\n').count() + 1;
+	let column = before
+		.rsplit('\n')
+		.next()
+		.unwrap_or_default()
+		.chars()
+		.count()
+		+ 1;
+	(line, column)
+}
+
+/// part of the path to a value of the toml document
+#[derive(Clone, Copy, Debug)]
+enum Key<'a> {
+	Name(&'a str),
+	Index(usize)
+}
+
+struct Checker<'a> {
+	doc: &'a ImDocument<&'a str>,
+	diagnostics: Vec<Diagnostic>,
+	/// Index at the document of each entry of the top level arrays,
+	/// if invalid entries were removed before parsing the config.
+	indices: HashMap<&'static str, Vec<usize>>
+}
+
+impl Checker<'_> {
+	/// Span of the value at `path`.
+	/// If the value does not exist (as example, because a single value was used instead of an array),
+	/// the span of the deepest existing parent is used.
+	fn span(&self, path: &[Key<'_>]) -> Option<Range<usize>> {
+		let mut item = self.doc.as_item();
+		let mut span = None;
+		for (depth, key) in path.iter().enumerate() {
+			let next = match (path[0], key) {
+				(Key::Name(array), Key::Index(index)) if depth == 1 => {
+					let index = self
+						.indices
+						.get(array)
+						.and_then(|indices| indices.get(*index))
+						.unwrap_or(index);
+					item.get(*index)
+				},
+				(_, Key::Name(name)) => item.get(*name),
+				(_, Key::Index(index)) => item.get(*index)
+			};
+			let Some(next) = next else { break };
+			item = next;
+			span = item.span().or(span);
+		}
+		span
+	}
+
+	fn push(&mut self, severity: Severity, message: String, path: &[Key<'_>]) {
+		let span = self.span(path);
+		self.diagnostics.push(Diagnostic {
+			severity,
+			message,
+			span
+		});
+	}
+
+	/// Parse the entries of the top level array `key` one by one, report and remove the invalid ones.
+	/// Return the indices of the remaining entries.
+	fn filter_entries<T: DeserializeOwned>(
+		&mut self,
+		table: &mut toml::Table,
+		key: &'static str
+	) -> Vec<usize> {
+		let Some(toml::Value::Array(entries)) = table.get_mut(key) else {
+			return Vec::new();
+		};
+		let mut indices = Vec::new();
+		for (i, entry) in mem::take(entries).into_iter().enumerate() {
+			match T::deserialize(entry.clone()) {
+				Ok(_) => {
+					indices.push(i);
+					entries.push(entry);
+				},
+				Err(err) => self.push(
+					Severity::Error,
+					format!("invalid {key}: {}", err.message()),
+					&[Key::Name(key), Key::Index(i)]
+				)
+			}
+		}
+		indices
+	}
+
+	/// Parse the config without its invalid `profile`, `download` and `remote_job` entries,
+	/// so one invalid entry does not hide the problems of the others.
+	/// `err` is the error of parsing the whole config.
+	fn parse_valid_entries(
+		&mut self,
+		text: &str,
+		err: toml::de::Error
+	) -> Option<Config> {
+		let Ok(mut table) = toml::from_str::<toml::Table>(text) else {
+			self.push_parse_error(&err);
+			return None;
+		};
+		let indices = [
+			(
+				"profile",
+				self.filter_entries::<Profile>(&mut table, "profile")
+			),
+			(
+				"download",
+				self.filter_entries::<Download>(&mut table, "download")
+			),
+			(
+				"remote_job",
+				self.filter_entries::<RemoteJob>(&mut table, "remote_job")
+			)
+		];
+		if self.diagnostics.is_empty() {
+			// the error is not caused by an entry
+			self.push_parse_error(&err);
+			return None;
+		}
+		match Config::deserialize(toml::Value::Table(table)) {
+			Ok(config) => {
+				self.indices = indices.into();
+				Some(config)
+			},
+			Err(err) => {
+				self.push(Severity::Error, err.message().to_owned(), &[]);
+				None
+			}
+		}
+	}
+
+	fn push_parse_error(&mut self, err: &toml::de::Error) {
+		self.diagnostics.push(Diagnostic {
+			severity: Severity::Error,
+			message: err.message().to_owned(),
+			span: err.span()
+		});
+	}
+
+	fn check_remote_url(&mut self, url: &str, path: &[Key<'_>]) {
+		match Url::parse(url) {
+			Err(err) => self.push(
+				Severity::Error,
+				format!("remote job {url:?} is not a valid url: {err}"),
+				path
+			),
+			Ok(url) if !matches!(url.scheme(), "http" | "https" | "file") => self.push(
+				Severity::Error,
+				format!("unsupported scheme {:?} of remote job {url}", url.scheme()),
+				path
+			),
+			Ok(_) => {}
+		}
+	}
+
+	fn check(&mut self, config: &Config) {
+		for (i, pattern) in config.include.iter().enumerate() {
+			if let Err(err) = glob::Pattern::new(pattern) {
+				self.push(
+					Severity::Error,
+					format!("invalid include pattern {pattern:?}: {err}"),
+					&[Key::Name("include"), Key::Index(i)]
+				);
+			}
+		}
+
+		let mut profile_names = HashMap::new();
+		for (i, profile) in config.profile.iter().enumerate() {
+			let path = [Key::Name("profile"), Key::Index(i)];
+			if let Err(err) = check_name(&profile.name) {
+				self.push(
+					Severity::Error,
+					format!("invalid profile name {:?}: {err}", profile.name),
+					&[path[0], path[1], Key::Name("name")]
+				);
+			}
+			match profile_names.entry(profile.name.as_str()) {
+				Entry::Occupied(_) => self.push(
+					Severity::Error,
+					format!("duplicate profile name {:?}", profile.name),
+					&[path[0], path[1], Key::Name("name")]
+				),
+				Entry::Vacant(entry) => {
+					entry.insert(i);
+				}
+			}
+			if profile.args.is_empty() {
+				self.push(
+					Severity::Warning,
+					format!("profile {:?} has no args", profile.name),
+					&[path[0], path[1], Key::Name("args")]
+				);
+			}
+		}
+
+		let mut used_profiles = HashSet::new();
+		let mut download_names = HashSet::new();
+		for (i, download) in config.download.iter().enumerate() {
+			let path = [Key::Name("download"), Key::Index(i)];
+			if let Err(err) = check_name(&download.name) {
+				self.push(
+					Severity::Error,
+					format!("invalid download name {:?}: {err}", download.name),
+					&[path[0], path[1], Key::Name("name")]
+				);
+			}
+			if !download_names.insert(download.name.as_str()) {
+				self.push(
+					Severity::Error,
+					format!("duplicate download name {:?}", download.name),
+					&[path[0], path[1], Key::Name("name")]
+				);
+			}
+			for (j, profile_name) in download.profile.iter().enumerate() {
+				if profile_names.contains_key(profile_name.as_str()) {
+					used_profiles.insert(profile_name.as_str());
+				} else {
+					self.push(
+						Severity::Error,
+						format!(
+							"unknown profile {profile_name:?} at download {:?}",
+							download.name
+						),
+						&[path[0], path[1], Key::Name("profile"), Key::Index(j)]
+					);
+				}
+			}
+			// yt-dlp does also support other inputs than urls, like ids or searches
+			for (j, url) in download.url.iter().enumerate() {
+				if let Err(err) = Url::parse(url) {
+					self.push(
+						Severity::Warning,
+						format!("{url:?} is not a valid url: {err}"),
+						&[path[0], path[1], Key::Name("url"), Key::Index(j)]
+					);
+				}
+			}
+		}
+
+		// Local profiles can also be used by remote jobs and included files.
+		// Removed invalid downloads might also use them.
+		let profiles = if config.remote_job.is_empty()
+			&& config.include.is_empty()
+			&& self.indices.is_empty()
+		{
+			config.profile.as_slice()
+		} else {
+			&[]
+		};
+		for (i, profile) in profiles.iter().enumerate() {
+			if !used_profiles.contains(profile.name.as_str()) {
+				self.push(
+					Severity::Warning,
+					format!("profile {:?} is not used by any download", profile.name),
+					&[Key::Name("profile"), Key::Index(i), Key::Name("name")]
+				);
+			}
+		}
+
+		for (i, remote_job) in config.remote_job.iter().enumerate() {
+			let url = &remote_job.url;
+			let path = [Key::Name("remote_job"), Key::Index(i), Key::Name("url")];
+			self.check_remote_url(url, &path);
+			for (j, mirror) in remote_job.mirrors.iter().enumerate() {
+				self.check_remote_url(mirror, &[
+					path[0],
+					path[1],
+					Key::Name("mirrors"),
+					Key::Index(j)
+				]);
+			}
+			if let Some(report_url) = &remote_job.report_url {
+				let path = [path[0], path[1], Key::Name("report_url")];
+				match Url::parse(report_url) {
+					Err(err) => self.push(
+						Severity::Error,
+						format!("report url {report_url:?} is not a valid url: {err}"),
+						&path
+					),
+					Ok(url) if !matches!(url.scheme(), "http" | "https") => self.push(
+						Severity::Error,
+						format!(
+							"unsupported scheme {:?} of report url {url}",
+							url.scheme()
+						),
+						&path
+					),
+					Ok(_) => {}
+				}
+			}
+			if let Some(namespace) = &remote_job.namespace {
+				if let Err(err) = check_name(namespace) {
+					self.push(
+						Severity::Error,
+						format!("invalid namespace {namespace:?}: {err}"),
+						&[path[0], path[1], Key::Name("namespace")]
+					);
+				}
+			}
+			if let Some(public_key) = &remote_job.public_key {
+				if let Err(err) = PublicKey::from_base64(public_key) {
+					self.push(
+						Severity::Error,
+						format!("invalid public key of remote job {url:?}: {err}"),
+						&[path[0], path[1], Key::Name("public_key")]
+					);
+				}
+			}
+		}
+	}
+}
+
+/// Check the content of a config file and return all problems found.
+pub fn validate_config(text: &str) -> Vec<Diagnostic> {
+	let doc = match ImDocument::parse(text) {
+		Ok(value) => value,
+		Err(err) => {
+			return vec![Diagnostic {
+				severity: Severity::Error,
+				message: err.message().to_owned(),
+				span: err.span()
+			}]
+		},
+	};
+	let mut checker = Checker {
+		doc: &doc,
+		diagnostics: Vec::new(),
+		indices: HashMap::new()
+	};
+	let config: Config = match toml::from_str(text) {
+		Ok(value) => value,
+		Err(err) => match checker.parse_valid_entries(text, err) {
+			Some(value) => value,
+			None => return checker.diagnostics
+		}
+	};
+	checker.check(&config);
+	checker.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn diagnostics() {
+		let text = r#"
+[[profile]]
+name = "audio"
+args = ["-x"]
+
+[[profile]]
+name = "unused"
+args = []
+
+[[download]]
+name = "music"
+url = "https://example.com/music"
+profile = ["audio", "video"]
+
+[[download]]
+name = "music"
+url = "https://example.com/more"
+profile = "audio"
+
+[[download]]
+name = "../music"
+url = "https://example.com/other"
+profile = "audio"
+"#;
+		let diagnostics: Vec<_> = validate_config(text)
+			.iter()
+			.map(|diagnostic| diagnostic.format(Path::new("config.toml"), text))
+			.collect();
+		assert_eq!(diagnostics, [
+			"config.toml:8:8: warning: profile \"unused\" has no args",
+			"config.toml:13:21: error: unknown profile \"video\" at download \"music\"",
+			"config.toml:16:8: error: duplicate download name \"music\"",
+			"config.toml:21:8: error: invalid download name \"../music\": name must not start with a dot or space",
+			"config.toml:7:8: warning: profile \"unused\" is not used by any download"
+		]);
+	}
+
+	#[test]
+	fn invalid_entries() {
+		let text = r#"
+[[profile]]
+name = "audio"
+args = ["-x"]
+
+[[download]]
+name = "missing-url"
+profile = "audio"
+
+[[download]]
+name = "bad-schedule"
+url = "https://example.com/a"
+profile = "audio"
+schedule = "0 25 * * *"
+
+[[download]]
+name = "music"
+url = "https://example.com/music"
+profile = "video"
+"#;
+		let diagnostics: Vec<_> = validate_config(text)
+			.iter()
+			.map(|diagnostic| diagnostic.format(Path::new("config.toml"), text))
+			.collect();
+		assert_eq!(diagnostics.len(), 3, "{diagnostics:#?}");
+		assert!(diagnostics[0].starts_with(
+			"config.toml:6:1: error: invalid download: missing field `url`"
+		));
+		assert!(diagnostics[1].starts_with(
+			"config.toml:10:1: error: invalid download: invalid cron expression"
+		));
+		assert_eq!(
+			diagnostics[2],
+			"config.toml:19:11: error: unknown profile \"video\" at download \"music\""
+		);
+	}
+}